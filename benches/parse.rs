@@ -0,0 +1,34 @@
+//! Compares the hand-written fast path with the PEG parser on typical engine output. Run with `cargo bench`.
+
+extern crate vampirc_uci;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use vampirc_uci::{parse_with_config, ParserConfig};
+
+const LINES: [&str; 6] = [
+    "info depth 24 seldepth 33 multipv 1 score cp 34 nodes 8765432 nps 2345678 hashfull 512 tbhits 0 time 3737 pv e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6 e1g1 f8e7 f1e1 b7b5 a4b3 d7d6",
+    "info depth 24 seldepth 35 multipv 2 score cp 21 upperbound nodes 8765432 nps 2345678 time 3737 pv d2d4 g8f6 c2c4 e7e6 g1f3 d7d5 b1c3 f8e7",
+    "info depth 25 currmove e2e4 currmovenumber 1",
+    "info depth 25 seldepth 36 multipv 1 score mate 12 wdl 1000 0 0 nodes 9876543 nps 2400000 time 4115 pv e2e4 e7e5",
+    "bestmove e2e4 ponder e7e5",
+    "position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 moves e7e5 g1f3 b8c6 f1b5 a7a6",
+];
+
+/// Parses all the lines with `config`.
+fn parse_lines(config: &ParserConfig) -> usize {
+    LINES.iter().map(|line| parse_with_config(black_box(line), config).unwrap().len()).sum()
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let fast_config = ParserConfig::default();
+    let slow_config = ParserConfig::default().fast_path(false);
+
+    let mut group = c.benchmark_group("engine output");
+    group.bench_function("fast path", |b| b.iter(|| parse_lines(&fast_config)));
+    group.bench_function("PEG parser", |b| b.iter(|| parse_lines(&slow_config)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+//! A `no_std` + `alloc` user of `vampirc-uci`. It is only built, never run.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::string::String;
+use core::alloc::{GlobalAlloc, Layout};
+use core::panic::PanicInfo;
+
+use vampirc_uci::{parse_one, parse_with_config, ParserConfig, Serializable, UciMessage, UciStreamParser};
+
+struct NullAllocator;
+
+unsafe impl GlobalAlloc for NullAllocator {
+    unsafe fn alloc(&self, _layout: Layout) -> *mut u8 {
+        core::ptr::null_mut()
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
+
+#[global_allocator]
+static ALLOCATOR: NullAllocator = NullAllocator;
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
+
+/// Parses a line and serializes the message back.
+pub fn round_trip(line: &str) -> usize {
+    let msg: UciMessage = parse_one(line);
+    let serialized: String = msg.serialize();
+
+    serialized.len()
+}
+
+/// Parses the input with the default configuration and through the stream parser.
+pub fn count_messages(input: &[u8]) -> usize {
+    let mut parser = UciStreamParser::new();
+    parser.feed(input);
+    parser.finish();
+
+    let mut count = 0;
+    while parser.next_message().is_some() {
+        count += 1;
+    }
+
+    count + parse_with_config("uci\nisready\n", &ParserConfig::default()).map_or(0, |ml| ml.len())
+}
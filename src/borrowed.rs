@@ -0,0 +1,675 @@
+//! The `borrowed` module contains `UciMessageRef`, a variant of `UciMessage` whose text fields borrow from the parsed
+//! input instead of being copied into `String`s. It is obtained from `parse_one_ref`, `try_parse_one_ref` or
+//! `parse_with_unknown_ref` and can be turned into a `UciMessage` with `into_owned()`.
+//!
+//! Lists of moves (such as the principal variation) are kept as a `UciMovesRef`, a slice of the input that is validated
+//! by the parser but only split into individual moves when iterated over, so parsing an `info` message does not
+//! allocate a `Vec` per move list.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+use core::str::SplitWhitespace;
+
+#[cfg(feature = "chess")]
+use chess::{ChessMove, File, Piece, Rank, Square};
+
+use crate::duration::UciDuration;
+use crate::error::{line_col, UciParseError, UciParseErrorKind};
+#[cfg(feature = "chess")]
+use crate::uci::null_move;
+#[cfg(not(feature = "chess"))]
+use crate::uci::{UciMove, UciPiece, UciSquare};
+use crate::uci::{
+    CommunicationDirection, Move, ProtectionState, UciFen, UciInfoAttribute, UciMessage, UciOptionConfig,
+    UciSearchControl, UciTimeControl,
+};
+
+/// A borrowed variant of `UciMessage`. See `UciMessage` for the description of each variant.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum UciMessageRef<'a> {
+    /// The `uci` engine-bound message.
+    Uci,
+
+    /// The `debug` engine-bound message.
+    Debug(bool),
+
+    /// The `isready` engine-bound message.
+    IsReady,
+
+    /// The `register` engine-bound message.
+    Register {
+        /// The `register later` message.
+        later: bool,
+
+        /// The name part of the `register <code> <name>` message.
+        name: Option<&'a str>,
+
+        /// The code part of the `register <code> <name>` message.
+        code: Option<&'a str>,
+    },
+
+    /// The `position` engine-bound message.
+    Position {
+        /// If `true`, it denotes the starting chess position.
+        startpos: bool,
+
+        /// If set, the FEN of the position, exactly as received.
+        fen: Option<&'a str>,
+
+        /// A list of moves to apply to the position.
+        moves: UciMovesRef<'a>,
+    },
+
+    /// The `setoption` engine-bound message.
+    SetOption {
+        /// The name of the option to set.
+        name: &'a str,
+
+        /// The value of the option to set. If the option has no value, this should be `None`.
+        value: Option<&'a str>,
+    },
+
+    /// The `ucinewgame` engine-bound message.
+    UciNewGame,
+
+    /// The `stop` engine-bound message.
+    Stop,
+
+    /// The `ponderhit` engine-bound message.
+    PonderHit,
+
+    /// The `quit` engine-bound message.
+    Quit,
+
+    /// The `go` engine-bound message.
+    Go {
+        /// Time-control-related `go` parameters (sub-commands).
+        time_control: Option<UciTimeControl>,
+
+        /// Search-related `go` parameters (sub-commands).
+        search_control: Option<UciSearchControl>,
+    },
+
+    /// The `id` GUI-bound message.
+    Id {
+        /// The name of the engine, possibly including the version.
+        name: Option<&'a str>,
+
+        /// The name of the author of the engine.
+        author: Option<&'a str>,
+    },
+
+    /// The `uciok` GUI-bound message.
+    UciOk,
+
+    /// The `readyok` GUI-bound message.
+    ReadyOk,
+
+    /// The `bestmove` GUI-bound message.
+    BestMove {
+        /// The move the engine thinks is the best one in the position.
+        best_move: Move,
+
+        /// The move the engine would like to ponder on.
+        ponder: Option<Move>,
+    },
+
+    /// The `copyprotection` GUI-bound message.
+    CopyProtection(ProtectionState),
+
+    /// The `registration` GUI-bound message.
+    Registration(ProtectionState),
+
+    /// The `option` GUI-bound message.
+    Option(UciOptionConfigRef<'a>),
+
+    /// The `info` GUI-bound message.
+    Info(Vec<UciInfoAttributeRef<'a>>),
+
+    /// Indicating unknown message.
+    Unknown(&'a str, Option<UciParseError>),
+}
+
+impl UciMessageRef<'_> {
+    /// Returns whether the command was meant for the engine or for the GUI.
+    pub fn direction(&self) -> CommunicationDirection {
+        match self {
+            UciMessageRef::Uci |
+            UciMessageRef::Debug(..) |
+            UciMessageRef::IsReady |
+            UciMessageRef::Register { .. } |
+            UciMessageRef::Position { .. } |
+            UciMessageRef::SetOption { .. } |
+            UciMessageRef::UciNewGame |
+            UciMessageRef::Stop |
+            UciMessageRef::PonderHit |
+            UciMessageRef::Quit |
+            UciMessageRef::Go { .. } => CommunicationDirection::GuiToEngine,
+            _ => CommunicationDirection::EngineToGui
+        }
+    }
+
+    /// Converts this message into an owned `UciMessage`, copying the borrowed text.
+    pub fn into_owned(self) -> UciMessage {
+        match self {
+            UciMessageRef::Uci => UciMessage::Uci,
+            UciMessageRef::Debug(on) => UciMessage::Debug(on),
+            UciMessageRef::IsReady => UciMessage::IsReady,
+            UciMessageRef::Register { later, name, code } => UciMessage::Register {
+                later,
+                name: name.map(String::from),
+                code: code.map(String::from),
+            },
+            UciMessageRef::Position { startpos, fen, moves } => UciMessage::Position {
+                startpos,
+                fen: fen.map(UciFen::from),
+                moves: moves.to_vec(),
+            },
+            UciMessageRef::SetOption { name, value } => UciMessage::SetOption {
+                name: name.to_string(),
+                value: value.map(String::from),
+            },
+            UciMessageRef::UciNewGame => UciMessage::UciNewGame,
+            UciMessageRef::Stop => UciMessage::Stop,
+            UciMessageRef::PonderHit => UciMessage::PonderHit,
+            UciMessageRef::Quit => UciMessage::Quit,
+            UciMessageRef::Go { time_control, search_control } => UciMessage::Go { time_control, search_control },
+            UciMessageRef::Id { name, author } => UciMessage::Id {
+                name: name.map(String::from),
+                author: author.map(String::from),
+            },
+            UciMessageRef::UciOk => UciMessage::UciOk,
+            UciMessageRef::ReadyOk => UciMessage::ReadyOk,
+            UciMessageRef::BestMove { best_move, ponder } => UciMessage::BestMove { best_move, ponder },
+            UciMessageRef::CopyProtection(ps) => UciMessage::CopyProtection(ps),
+            UciMessageRef::Registration(ps) => UciMessage::Registration(ps),
+            UciMessageRef::Option(config) => UciMessage::Option(config.into_owned()),
+            UciMessageRef::Info(attributes) => {
+                UciMessage::Info(attributes.into_iter().map(UciInfoAttributeRef::into_owned).collect())
+            }
+            UciMessageRef::Unknown(text, e) => UciMessage::Unknown(text.to_string(), e),
+        }
+    }
+}
+
+impl From<UciMessageRef<'_>> for UciMessage {
+    fn from(m: UciMessageRef<'_>) -> Self {
+        m.into_owned()
+    }
+}
+
+/// A borrowed variant of `UciOptionConfig`. See `UciOptionConfig` for the description of each variant.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum UciOptionConfigRef<'a> {
+    /// The option of type `check` (a boolean).
+    Check {
+        /// The name of the option.
+        name: &'a str,
+
+        /// The default value of this `bool` property.
+        default: Option<bool>,
+    },
+
+    /// The option of type `spin` (a signed integer).
+    Spin {
+        /// The name of the option.
+        name: &'a str,
+
+        /// The default value of this integer property.
+        default: Option<i64>,
+
+        /// The minimal value of this integer property.
+        min: Option<i64>,
+
+        /// The maximal value of this integer property.
+        max: Option<i64>,
+    },
+
+    /// The option of type `combo` (a list of strings).
+    Combo {
+        /// The name of the option.
+        name: &'a str,
+
+        /// The default value for this list of strings.
+        default: Option<&'a str>,
+
+        /// The list of acceptable strings.
+        var: Vec<&'a str>,
+    },
+
+    /// The option of type `button` (an action).
+    Button {
+        /// The name of the option.
+        name: &'a str,
+    },
+
+    /// The option of type `string` (a string, unsurprisingly).
+    String {
+        /// The name of the option.
+        name: &'a str,
+
+        /// The default value of this string option.
+        default: Option<&'a str>,
+    },
+}
+
+impl UciOptionConfigRef<'_> {
+    /// Converts this option into an owned `UciOptionConfig`, copying the borrowed text.
+    pub fn into_owned(self) -> UciOptionConfig {
+        match self {
+            UciOptionConfigRef::Check { name, default } => UciOptionConfig::Check {
+                name: name.to_string(),
+                default,
+            },
+            UciOptionConfigRef::Spin { name, default, min, max } => UciOptionConfig::Spin {
+                name: name.to_string(),
+                default,
+                min,
+                max,
+            },
+            UciOptionConfigRef::Combo { name, default, var } => UciOptionConfig::Combo {
+                name: name.to_string(),
+                default: default.map(String::from),
+                var: var.iter().map(|v| v.to_string()).collect(),
+            },
+            UciOptionConfigRef::Button { name } => UciOptionConfig::Button { name: name.to_string() },
+            UciOptionConfigRef::String { name, default } => UciOptionConfig::String {
+                name: name.to_string(),
+                default: default.map(String::from),
+            },
+        }
+    }
+}
+
+/// A borrowed variant of `UciInfoAttribute`. See `UciInfoAttribute` for the description of each variant.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub enum UciInfoAttributeRef<'a> {
+    /// The `info depth` message.
+    Depth(u16),
+
+    /// The `info seldepth` message.
+    SelDepth(u16),
+
+    /// The `info time` message.
+    Time(UciDuration),
+
+    /// The `info nodes` message.
+    Nodes(u64),
+
+    /// The `info pv` message (best line move sequence).
+    Pv(UciMovesRef<'a>),
+
+    /// The `info multipv` message.
+    MultiPv(u16),
+
+    /// The `info score` message.
+    Score {
+        /// The score in centipawns.
+        cp: Option<i32>,
+
+        /// Mate coming up in this many moves. Negative value means the engine is getting mated.
+        mate: Option<i16>,
+
+        /// The value sent is the lower bound.
+        lower_bound: Option<bool>,
+
+        /// The value sent is the upper bound.
+        upper_bound: Option<bool>,
+    },
+
+    /// The `info wdl` message.
+    Wdl {
+        /// The expected number of wins, per mille.
+        win: u16,
+
+        /// The expected number of draws, per mille.
+        draw: u16,
+
+        /// The expected number of losses, per mille.
+        loss: u16,
+    },
+
+    /// The `info currmove` message (current move).
+    CurrMove(Move),
+
+    /// The `info currmovenumber` message (current move number).
+    CurrMoveNum(u16),
+
+    /// The `info hashfull` message (the occupancy of hashing tables in permills).
+    HashFull(u16),
+
+    /// The `info nps` message (nodes per second).
+    Nps(u64),
+
+    /// The `info tbhits` message (end-game table-base hits).
+    TbHits(u64),
+
+    /// The `info sbhits` message (I guess some Shredder-specific end-game table-base hits?).
+    SbHits(u64),
+
+    /// The `info cpuload` message (CPU load in permills).
+    CpuLoad(u16),
+
+    /// The `info string` message (a string the GUI should display).
+    String(&'a str),
+
+    /// The `info refutation` message (the first move is the move being refuted).
+    Refutation(UciMovesRef<'a>),
+
+    /// The `info currline` message (current line being calculated on a CPU).
+    CurrLine {
+        /// The CPU number calculating this line.
+        cpu_nr: Option<u16>,
+
+        /// The line being calculated.
+        line: UciMovesRef<'a>,
+    },
+
+    /// Any other info line in the format `(name, value)`.
+    Any(&'a str, &'a str),
+}
+
+impl UciInfoAttributeRef<'_> {
+    /// Converts this attribute into an owned `UciInfoAttribute`, copying the borrowed text.
+    pub fn into_owned(self) -> UciInfoAttribute {
+        match self {
+            UciInfoAttributeRef::Depth(d) => UciInfoAttribute::Depth(d),
+            UciInfoAttributeRef::SelDepth(d) => UciInfoAttribute::SelDepth(d),
+            UciInfoAttributeRef::Time(t) => UciInfoAttribute::Time(t),
+            UciInfoAttributeRef::Nodes(n) => UciInfoAttribute::Nodes(n),
+            UciInfoAttributeRef::Pv(moves) => UciInfoAttribute::Pv(moves.to_vec()),
+            UciInfoAttributeRef::MultiPv(n) => UciInfoAttribute::MultiPv(n),
+            UciInfoAttributeRef::Score { cp, mate, lower_bound, upper_bound } => UciInfoAttribute::Score {
+                cp,
+                mate,
+                lower_bound,
+                upper_bound,
+            },
+            UciInfoAttributeRef::Wdl { win, draw, loss } => UciInfoAttribute::Wdl {
+                win,
+                draw,
+                loss,
+            },
+            UciInfoAttributeRef::CurrMove(m) => UciInfoAttribute::CurrMove(m),
+            UciInfoAttributeRef::CurrMoveNum(n) => UciInfoAttribute::CurrMoveNum(n),
+            UciInfoAttributeRef::HashFull(n) => UciInfoAttribute::HashFull(n),
+            UciInfoAttributeRef::Nps(n) => UciInfoAttribute::Nps(n),
+            UciInfoAttributeRef::TbHits(n) => UciInfoAttribute::TbHits(n),
+            UciInfoAttributeRef::SbHits(n) => UciInfoAttribute::SbHits(n),
+            UciInfoAttributeRef::CpuLoad(n) => UciInfoAttribute::CpuLoad(n),
+            UciInfoAttributeRef::String(s) => UciInfoAttribute::String(s.to_string()),
+            UciInfoAttributeRef::Refutation(moves) => UciInfoAttribute::Refutation(moves.to_vec()),
+            UciInfoAttributeRef::CurrLine { cpu_nr, line } => UciInfoAttribute::CurrLine {
+                cpu_nr,
+                line: line.to_vec(),
+            },
+            UciInfoAttributeRef::Any(name, value) => UciInfoAttribute::Any(name.to_string(), value.to_string()),
+        }
+    }
+}
+
+/// A list of moves borrowed from the input, such as the principal variation of an `info` message. The moves are
+/// validated when the message is parsed, but only decoded when iterated over, so the list does not allocate. Two
+/// lists are equal if their moves are equal, regardless of the case and the spacing of the text.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{UciMovesRef, UciParseErrorKind};
+///
+/// let moves = UciMovesRef::parse("e2e4 e7e5 g1f3").unwrap();
+/// assert_eq!(moves.len(), 3);
+///
+/// let err = UciMovesRef::parse("e2e4 e7e9 g1f3").unwrap_err();
+/// assert_eq!(err.kind, UciParseErrorKind::BadMove);
+/// assert_eq!((err.column, err.text.as_str()), (6, "e7e9"));
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct UciMovesRef<'a>(&'a str);
+
+impl<'a> UciMovesRef<'a> {
+    /// Creates a move list from a string of whitespace-separated moves, which must have already been validated by the
+    /// parser.
+    pub(crate) fn new(s: &'a str) -> UciMovesRef<'a> {
+        UciMovesRef(s)
+    }
+
+    /// Parses a string of whitespace-separated moves in the UCI notation, case-insensitively. Returns a
+    /// `UciParseErrorKind::BadMove` error pointing at the first token that is not a valid move.
+    pub fn parse(s: &'a str) -> Result<UciMovesRef<'a>, UciParseError> {
+        for token in s.split(char::is_whitespace).filter(|t| !t.is_empty()) {
+            if parse_move_str::<Move>(token).is_none() {
+                let pos = token.as_ptr() as usize - s.as_ptr() as usize;
+                let (line, column) = line_col(s, pos);
+
+                return Err(UciParseError::new(
+                    UciParseErrorKind::BadMove,
+                    line,
+                    column,
+                    token,
+                    vec![String::from("a move")],
+                ));
+            }
+        }
+
+        Ok(UciMovesRef::new(s.trim()))
+    }
+
+    /// Returns the moves as they appear in the input.
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    /// Returns the number of moves in the list.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the list contains no moves.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Returns an iterator over the moves in the list.
+    pub fn iter(&self) -> UciMovesIter<'a> {
+        UciMovesIter {
+            tokens: self.0.split_whitespace(),
+        }
+    }
+
+    /// Decodes the moves into a `Vec`.
+    pub fn to_vec(&self) -> Vec<Move> {
+        self.iter().collect()
+    }
+}
+
+impl PartialEq for UciMovesRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for UciMovesRef<'_> {}
+
+impl Hash for UciMovesRef<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for m in self.iter() {
+            m.hash(state);
+        }
+    }
+}
+
+impl<'a> IntoIterator for UciMovesRef<'a> {
+    type Item = Move;
+    type IntoIter = UciMovesIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the moves of a `UciMovesRef`.
+#[derive(Clone, Debug)]
+pub struct UciMovesIter<'a> {
+    tokens: SplitWhitespace<'a>,
+}
+
+impl Iterator for UciMovesIter<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Every token was validated when the list was created, so none of them is skipped.
+        self.tokens.next().map(|t| parse_move_str(t).expect("moves are validated when the list is created"))
+    }
+}
+
+/// A move type that `parse_move_str` can build: `UciMove`, or `ChessMove` with the `chess` feature.
+pub(crate) trait MoveFromParts: Sized {
+    /// The type of the promotion piece.
+    type Piece: Copy;
+
+    /// The pieces a pawn can be promoted to, in the order of the characters `q`, `r`, `n` and `b`.
+    const PROMOTIONS: [Self::Piece; 4];
+
+    /// Returns the null move.
+    fn null() -> Self;
+
+    /// Creates a move from the lowercase file characters and the 0-based rank indices of its squares.
+    fn from_parts(from: (u8, u8), to: (u8, u8), promotion: Option<Self::Piece>) -> Self;
+}
+
+#[cfg(not(feature = "chess"))]
+impl MoveFromParts for UciMove {
+    type Piece = UciPiece;
+
+    const PROMOTIONS: [UciPiece; 4] = [UciPiece::Queen, UciPiece::Rook, UciPiece::Knight, UciPiece::Bishop];
+
+    fn null() -> UciMove {
+        UciMove::null()
+    }
+
+    fn from_parts(from: (u8, u8), to: (u8, u8), promotion: Option<UciPiece>) -> UciMove {
+        UciMove {
+            from: UciSquare::from(from.0 as char, from.1 + 1),
+            to: UciSquare::from(to.0 as char, to.1 + 1),
+            promotion,
+        }
+    }
+}
+
+#[cfg(feature = "chess")]
+impl MoveFromParts for ChessMove {
+    type Piece = Piece;
+
+    const PROMOTIONS: [Piece; 4] = [Piece::Queen, Piece::Rook, Piece::Knight, Piece::Bishop];
+
+    fn null() -> ChessMove {
+        null_move()
+    }
+
+    fn from_parts(from: (u8, u8), to: (u8, u8), promotion: Option<Piece>) -> ChessMove {
+        let square = |(file, rank): (u8, u8)| {
+            Square::make_square(Rank::from_index(rank as usize), File::from_index((file - b'a') as usize))
+        };
+
+        ChessMove::new(square(from), square(to), promotion)
+    }
+}
+
+/// Parses a single move in the UCI notation (such as `e2e4`, `a7a8q`, `0000` or `(none)`), case-insensitively.
+pub(crate) fn parse_move_str<M: MoveFromParts>(s: &str) -> Option<M> {
+    if s == "0000" || s.eq_ignore_ascii_case("(none)") {
+        return Some(M::null());
+    }
+
+    let b = s.as_bytes();
+    let (from, to) = match b {
+        [_, _, _, _] | [_, _, _, _, _] => (file_rank(b[0], b[1])?, file_rank(b[2], b[3])?),
+        _ => return None,
+    };
+    let promotion = match b.get(4) {
+        None => None,
+        Some(p) => {
+            let i = b"qrnb".iter().position(|c| *c == p.to_ascii_lowercase())?;
+            Some(M::PROMOTIONS[i])
+        }
+    };
+
+    Some(M::from_parts(from, to, promotion))
+}
+
+/// Returns the lowercase file character and the 0-based rank index of a square, if valid.
+pub(crate) fn file_rank(file: u8, rank: u8) -> Option<(u8, u8)> {
+    let file = file.to_ascii_lowercase();
+
+    if (b'a'..=b'h').contains(&file) && (b'1'..=b'8').contains(&rank) {
+        Some((file, rank - b'1'))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_ref() {
+        let moves = UciMovesRef::parse("e2e4  E7E5 a7a8q 0000").unwrap();
+        assert_eq!(moves.len(), 4);
+        assert!(!moves.is_empty());
+        assert!(UciMovesRef::default().is_empty());
+        assert_eq!(moves.as_str(), "e2e4  E7E5 a7a8q 0000");
+
+        let owned = moves.to_vec();
+        assert_eq!(owned.len(), 4);
+        assert_eq!(moves.iter().next(), parse_move_str("e2e4"));
+        assert_eq!(moves.into_iter().nth(1), parse_move_str("e7e5"));
+
+        assert_eq!(moves, UciMovesRef::parse(" E2E4 e7e5\ta7a8Q (none)\n").unwrap());
+        assert_ne!(moves, UciMovesRef::parse("e2e4 e7e5 a7a8q").unwrap());
+
+        for invalid in ["e2e9", "e2e4x", "e2", "e7e8k"].iter() {
+            assert_eq!(parse_move_str(invalid), None::<Move>);
+        }
+    }
+
+    #[test]
+    fn test_moves_ref_bad_token() {
+        for (input, column, text) in [("e2e4 e2e9 e7e5", 6, "e2e9"), ("e2", 1, "e2"), ("e2e4\te7e5  g1f9", 12, "g1f9")]
+            .iter()
+        {
+            let err = UciMovesRef::parse(input).unwrap_err();
+            assert_eq!(err.kind, UciParseErrorKind::BadMove);
+            assert_eq!((err.line, err.column, err.text.as_str()), (1, *column, *text));
+        }
+
+        assert_eq!(UciMovesRef::parse("   "), Ok(UciMovesRef::default()));
+    }
+
+    #[test]
+    #[cfg(not(feature = "chess"))]
+    fn test_parse_move_str() {
+        assert_eq!(
+            parse_move_str("A7a8Q"),
+            Some(UciMove {
+                from: UciSquare::from('a', 7),
+                to: UciSquare::from('a', 8),
+                promotion: Some(UciPiece::Queen),
+            })
+        );
+        assert_eq!(parse_move_str("(none)"), Some(UciMove::null()));
+    }
+
+    #[test]
+    #[cfg(feature = "chess")]
+    fn test_parse_move_str() {
+        assert_eq!(
+            parse_move_str("A7a8Q"),
+            Some(ChessMove::new(Square::A7, Square::A8, Some(Piece::Queen)))
+        );
+        assert_eq!(parse_move_str("(none)"), Some(null_move()));
+    }
+}
@@ -0,0 +1,425 @@
+//! The `chess960` module contains support for the [Chess960](https://en.wikipedia.org/wiki/Fischer_random_chess)
+//! castling notation, used by engines that have the `UCI_Chess960` option enabled.
+//!
+//! In Chess960 mode, castling is sent as the king capturing its own rook (`e1h1` instead of `e1g1`), and positions
+//! use [X-FEN](https://en.wikipedia.org/wiki/X-FEN) or Shredder-FEN castling fields (`HAha` instead of `KQkq`).
+
+use alloc::vec::Vec;
+
+#[cfg(feature = "chess")]
+use chess::{ChessMove, Square, ALL_SQUARES};
+
+#[cfg(feature = "chess")]
+use crate::uci::is_null_move;
+use crate::uci::{Move, UciFen, UciMessage};
+#[cfg(not(feature = "chess"))]
+use crate::uci::{UciMove, UciSquare};
+
+/// The piece placement of the standard starting position.
+const STARTPOS_PLACEMENT: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+
+/// Specifies the notation used for castling moves.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub enum CastlingMode {
+    /// The standard UCI notation, where castling is sent as the king's move to the `g` or `c` file (`e1g1`). This is
+    /// the default.
+    ///
+    /// A Chess960 castling move in which the king does not move (such as the king on `g1` castling with the rook on
+    /// `h1`) cannot be written in this notation, so it is kept in the Chess960 notation.
+    #[default]
+    Standard,
+
+    /// The Chess960 (`UCI_Chess960`) notation, where castling is sent as the king capturing its own rook (`e1h1`).
+    Chess960,
+}
+
+/// Constructs the castling move of the king on the `king` square with the rook on the `rook` square, in the notation
+/// specified by `mode`.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{castling_move, CastlingMode, UciMove, UciSquare};
+///
+/// let king = UciSquare::from('e', 1);
+/// let rook = UciSquare::from('h', 1);
+///
+/// assert_eq!(castling_move(king, rook, CastlingMode::Standard).to_string(), "e1g1");
+/// assert_eq!(castling_move(king, rook, CastlingMode::Chess960).to_string(), "e1h1");
+/// ```
+#[cfg(not(feature = "chess"))]
+pub fn castling_move(king: UciSquare, rook: UciSquare, mode: CastlingMode) -> UciMove {
+    match mode {
+        CastlingMode::Standard => {
+            let file = if rook.file > king.file { 'g' } else { 'c' };
+            UciMove::from_to(king, UciSquare::from(file, king.rank))
+        }
+        CastlingMode::Chess960 => UciMove::from_to(king, rook),
+    }
+}
+
+/// Constructs the castling move of the king on the `king` square with the rook on the `rook` square, in the notation
+/// specified by `mode`.
+#[cfg(feature = "chess")]
+pub fn castling_move(king: Square, rook: Square, mode: CastlingMode) -> ChessMove {
+    match mode {
+        CastlingMode::Standard => {
+            let king_idx = king.to_index();
+            let file = if rook.to_index() % 8 > king_idx % 8 { 6 } else { 2 };
+            ChessMove::new(king, ALL_SQUARES[king_idx - king_idx % 8 + file], None)
+        }
+        CastlingMode::Chess960 => ChessMove::new(king, rook, None),
+    }
+}
+
+/// Rewrites all castling moves in `moves`, played from the position described by `fen` (or from the starting position,
+/// if `fen` is `None`), into the notation specified by `mode`. Castling moves are recognised in both notations. Moves
+/// that are not castling moves are returned unchanged, as are all the moves if the piece placement of the FEN cannot
+/// be read.
+pub fn convert_castling_moves(fen: Option<&UciFen>, moves: &[Move], mode: CastlingMode) -> Vec<Move> {
+    do_convert_castling_moves(fen, moves, mode)
+}
+
+fn do_convert_castling_moves<M: TrackedMove>(fen: Option<&UciFen>, moves: &[M], mode: CastlingMode) -> Vec<M> {
+    let placement = match fen {
+        Some(f) => f.as_str().split_whitespace().next().unwrap_or(""),
+        None => STARTPOS_PLACEMENT,
+    };
+
+    let mut board = match Placement::from_fen(placement) {
+        Some(b) => b,
+        None => return moves.to_vec(),
+    };
+
+    moves
+        .iter()
+        .map(|m| match m.squares() {
+            Some((from, to)) => m.with_to(board.play(from, to, m.promotion_char(), mode)),
+            None => *m,
+        })
+        .collect()
+}
+
+/// Remembers the last `position` message of a sequence of messages, so that the moves in the messages that follow it
+/// (`bestmove`, `info pv`...) can be converted with `UciMessage::convert_castling_after`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CastlingTracker {
+    position: Option<UciMessage>,
+}
+
+impl CastlingTracker {
+    /// Converts the castling moves in `msg` into the notation specified by `mode`, if any. Messages that precede the
+    /// first `position` message are converted with `UciMessage::convert_castling`.
+    pub(crate) fn convert(&mut self, msg: UciMessage, mode: Option<CastlingMode>) -> UciMessage {
+        let mode = match mode {
+            Some(mode) => mode,
+            None => return msg,
+        };
+
+        let converted = match &self.position {
+            Some(position) => msg.convert_castling_after(position, mode),
+            None => msg.convert_castling(mode),
+        };
+
+        if let UciMessage::Position { .. } = converted {
+            self.position = Some(converted.clone());
+        }
+
+        converted
+    }
+}
+
+/// Validates the castling field of the `fen` against its piece placement. Standard (`KQkq`), Shredder-FEN (`HAha`) and
+/// X-FEN (a mix of both) castling rights are accepted. Each right must refer to a rook of the right colour on the back
+/// rank, on the correct side of the king, and there can be at most one right per side for each colour.
+pub(crate) fn castling_rights_valid(fen: &str) -> bool {
+    let mut fields = fen.split_whitespace();
+    let board = match fields.next().and_then(Placement::from_fen) {
+        Some(b) => b,
+        None => return false,
+    };
+
+    let castling = match fields.nth(1) {
+        Some(c) => c,
+        None => return false,
+    };
+
+    if castling == "-" {
+        return true;
+    }
+
+    let mut seen: Vec<(bool, bool)> = Vec::with_capacity(4);
+    for c in castling.chars() {
+        let white = c.is_ascii_uppercase();
+        let back_rank = if white { 0 } else { 56 };
+        let king = if white { 'K' } else { 'k' };
+        let rook = if white { 'R' } else { 'r' };
+
+        let king_file = match (0..8).find(|f| board.0[back_rank + f] == Some(king)) {
+            Some(f) => f,
+            None => return false,
+        };
+
+        let king_side = match c.to_ascii_lowercase() {
+            'k' => {
+                if board.outermost_rook(back_rank + king_file, true).is_none() {
+                    return false;
+                }
+                true
+            }
+            'q' => {
+                if board.outermost_rook(back_rank + king_file, false).is_none() {
+                    return false;
+                }
+                false
+            }
+            f @ 'a'..='h' => {
+                let rook_file = (f as u8 - b'a') as usize;
+                if rook_file == king_file || board.0[back_rank + rook_file] != Some(rook) {
+                    return false;
+                }
+                rook_file > king_file
+            }
+            _ => return false,
+        };
+
+        if seen.contains(&(white, king_side)) {
+            return false;
+        }
+        seen.push((white, king_side));
+    }
+
+    true
+}
+
+/// A minimal representation of the pieces on the board, indexed by square (`a1` is `0`, `h8` is `63`), with the pieces
+/// stored as their FEN characters. It is just enough to recognise castling moves in a `position` move list.
+struct Placement([Option<char>; 64]);
+
+impl Placement {
+    /// Reads the piece placement field of a FEN string.
+    fn from_fen(placement: &str) -> Option<Placement> {
+        let mut squares = [None; 64];
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return None;
+        }
+
+        for (i, rank) in ranks.iter().enumerate() {
+            let base = (7 - i) * 8;
+            let mut file = 0;
+            for c in rank.chars() {
+                if let Some(d) = c.to_digit(10) {
+                    file += d as usize;
+                } else {
+                    if file >= 8 {
+                        return None;
+                    }
+                    squares[base + file] = Some(c);
+                    file += 1;
+                }
+            }
+
+            if file != 8 {
+                return None;
+            }
+        }
+
+        Some(Placement(squares))
+    }
+
+    /// Returns the square of the rook farthest from the king on `king`'s rank, on the king's side if `king_side` is
+    /// `true`, or on the queen's side otherwise.
+    fn outermost_rook(&self, king: usize, king_side: bool) -> Option<usize> {
+        let rook = if self.0[king]?.is_ascii_uppercase() { 'R' } else { 'r' };
+        let rank = king - king % 8;
+        if king_side {
+            (king + 1..rank + 8).rev().find(|&sq| self.0[sq] == Some(rook))
+        } else {
+            (rank..king).find(|&sq| self.0[sq] == Some(rook))
+        }
+    }
+
+    /// Plays the move from `from` to `to` on the board, and returns the destination square of the move in the notation
+    /// specified by `mode`. Only the destination of castling moves is ever changed.
+    fn play(&mut self, from: usize, to: usize, promotion: Option<char>, mode: CastlingMode) -> usize {
+        let piece = match self.0[from] {
+            Some(p) => p,
+            None => return to,
+        };
+
+        let white = piece.is_ascii_uppercase();
+        let same_rank = from / 8 == to / 8;
+
+        if piece.eq_ignore_ascii_case(&'k') && same_rank {
+            let own_rook = if white { 'R' } else { 'r' };
+
+            let rook = if self.0[to] == Some(own_rook) {
+                // Chess960 notation: the king "captures" its own rook.
+                Some(to)
+            } else if from % 8 == 4 && (to % 8 == 6 || to % 8 == 2) {
+                // Standard notation: the king moves two squares from the `e` file.
+                self.outermost_rook(from, to % 8 == 6)
+            } else {
+                None
+            };
+
+            if let Some(rook) = rook {
+                let king_side = rook > from;
+                let rank = from - from % 8;
+                let (king_to, rook_to) = if king_side { (rank + 6, rank + 5) } else { (rank + 2, rank + 3) };
+
+                self.0[from] = None;
+                self.0[rook] = None;
+                self.0[king_to] = Some(piece);
+                self.0[rook_to] = Some(own_rook);
+
+                return match mode {
+                    // A king that stays on its square could only be written as a move to itself (`g1g1`).
+                    CastlingMode::Standard if king_to != from => king_to,
+                    _ => rook,
+                };
+            }
+        }
+
+        if piece.eq_ignore_ascii_case(&'p') && from % 8 != to % 8 && self.0[to].is_none() {
+            // En passant.
+            self.0[from - from % 8 + to % 8] = None;
+        }
+
+        self.0[from] = None;
+        self.0[to] = match promotion {
+            Some(p) if white => Some(p.to_ascii_uppercase()),
+            Some(p) => Some(p.to_ascii_lowercase()),
+            None => Some(piece),
+        };
+
+        to
+    }
+}
+
+/// Abstracts over the move representations (`UciMove` or `ChessMove`) for the purposes of castling conversion.
+trait TrackedMove: Copy {
+    /// Returns the indices of the source and destination squares, or `None` for the null move.
+    fn squares(&self) -> Option<(usize, usize)>;
+
+    /// Returns the promotion piece as a character, if any.
+    fn promotion_char(&self) -> Option<char>;
+
+    /// Returns a copy of this move with the destination square replaced.
+    fn with_to(&self, to: usize) -> Self;
+}
+
+#[cfg(not(feature = "chess"))]
+fn square_index(sq: UciSquare) -> Option<usize> {
+    let file = sq.file.to_ascii_lowercase();
+    if !('a'..='h').contains(&file) || !(1..=8).contains(&sq.rank) {
+        return None;
+    }
+
+    Some((sq.rank as usize - 1) * 8 + (file as u8 - b'a') as usize)
+}
+
+#[cfg(not(feature = "chess"))]
+impl TrackedMove for UciMove {
+    fn squares(&self) -> Option<(usize, usize)> {
+        if self.is_null() {
+            return None;
+        }
+
+        Some((square_index(self.from)?, square_index(self.to)?))
+    }
+
+    fn promotion_char(&self) -> Option<char> {
+        self.promotion.and_then(|p| p.as_char())
+    }
+
+    fn with_to(&self, to: usize) -> Self {
+        UciMove {
+            from: self.from,
+            to: UciSquare::from((b'a' + (to % 8) as u8) as char, (to / 8 + 1) as u8),
+            promotion: self.promotion,
+        }
+    }
+}
+
+#[cfg(feature = "chess")]
+impl TrackedMove for ChessMove {
+    fn squares(&self) -> Option<(usize, usize)> {
+        if is_null_move(self) {
+            return None;
+        }
+
+        Some((self.get_source().to_index(), self.get_dest().to_index()))
+    }
+
+    fn promotion_char(&self) -> Option<char> {
+        self.get_promotion().map(|p| p.to_string(chess::Color::Black).chars().next().unwrap_or('q'))
+    }
+
+    fn with_to(&self, to: usize) -> Self {
+        ChessMove::new(self.get_source(), ALL_SQUARES[to], self.get_promotion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_castling_rights_standard() {
+        assert!(castling_rights_valid("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        assert!(castling_rights_valid("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"));
+    }
+
+    #[test]
+    fn test_castling_rights_shredder() {
+        assert!(castling_rights_valid("bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w GEge - 0 1"));
+        assert!(castling_rights_valid("bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w KQkq - 0 1"));
+        assert!(castling_rights_valid("bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w GQge - 0 1"));
+    }
+
+    #[test]
+    fn test_castling_rights_invalid() {
+        // No rook on the f file.
+        assert!(!castling_rights_valid("bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w FEge - 0 1"));
+        // Two rights on the king's side.
+        assert!(!castling_rights_valid("bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w KGge - 0 1"));
+        // No king on the back rank.
+        assert!(!castling_rights_valid("rnbq1bnr/pppppppp/8/4k3/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+    }
+
+    #[cfg(not(feature = "chess"))]
+    #[test]
+    fn test_convert_castling_moves() {
+        let fen = UciFen::from("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let moves = vec![
+            UciMove::from_to(UciSquare::from('e', 1), UciSquare::from('g', 1)),
+            UciMove::from_to(UciSquare::from('e', 8), UciSquare::from('a', 8)),
+            UciMove::from_to(UciSquare::from('f', 1), UciSquare::from('f', 7)),
+        ];
+
+        let moves_960 = convert_castling_moves(Some(&fen), &moves, CastlingMode::Chess960);
+        let serialized: Vec<String> = moves_960.iter().map(|m| m.to_string()).collect();
+        assert_eq!(serialized, vec!["e1h1", "e8a8", "f1f7"]);
+
+        let moves_std = convert_castling_moves(Some(&fen), &moves_960, CastlingMode::Standard);
+        let serialized: Vec<String> = moves_std.iter().map(|m| m.to_string()).collect();
+        assert_eq!(serialized, vec!["e1g1", "e8c8", "f1f7"]);
+    }
+
+    #[cfg(not(feature = "chess"))]
+    #[test]
+    fn test_convert_castling_king_does_not_move() {
+        // The king on g1 castles with the rook on h1 and stays on g1, which has no standard notation.
+        let fen = UciFen::from("rk5r/8/8/8/8/8/8/6KR w HAha - 0 1");
+        let moves = vec![
+            UciMove::from_to(UciSquare::from('g', 1), UciSquare::from('h', 1)),
+            UciMove::from_to(UciSquare::from('b', 8), UciSquare::from('a', 8)),
+        ];
+
+        let moves_std = convert_castling_moves(Some(&fen), &moves, CastlingMode::Standard);
+        let serialized: Vec<String> = moves_std.iter().map(|m| m.to_string()).collect();
+        assert_eq!(serialized, vec!["g1h1", "b8c8"]);
+    }
+}
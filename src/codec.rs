@@ -0,0 +1,278 @@
+//! The `codec` module (available with the `tokio` feature) contains `UciCodec`, which frames UCI messages on
+//! asynchronous byte streams, such as the standard input and output pipes of an engine process run under tokio.
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::chess960::CastlingTracker;
+use crate::config::ParserConfig;
+use crate::error::UciReadError;
+use crate::parser::{parse_byte_line, too_long_byte_line};
+use crate::uci::{ByteVecUciMessage, UciMessage};
+
+/// A codec for use with `tokio_util::codec::FramedRead` and `FramedWrite` (or `Framed`), which decodes the input
+/// line by line into `UciMessage`s and encodes `UciMessage`s as their serialized form followed by `\n`.
+///
+/// Lines are decoded like in `parse_bytes_with_config`, according to the `ParserConfig` the codec was created with:
+/// empty lines are skipped, `\r\n` line endings are accepted, invalid UTF-8 is replaced with `U+FFFD` and, by default,
+/// unrecognised lines are returned as `UciMessage::Unknown`. At most `ParserConfig::max_line_length` bytes of a line
+/// are buffered. As soon as a line exceeds the limit, the rest of it is discarded and the line results in a
+/// `UciParseErrorKind::LineTooLong` error, which is subject to `ParserConfig::unknown_messages` like any other parse
+/// error. Note that a `FramedRead` ends after the first error returned by its decoder.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures_util::{SinkExt, StreamExt};
+/// use tokio::process::Command;
+/// use tokio_util::codec::{FramedRead, FramedWrite};
+/// use vampirc_uci::{UciCodec, UciMessage};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut engine = Command::new("stockfish")
+///     .stdin(std::process::Stdio::piped())
+///     .stdout(std::process::Stdio::piped())
+///     .spawn()?;
+///
+/// let mut to_engine = FramedWrite::new(engine.stdin.take().unwrap(), UciCodec::new());
+/// let mut from_engine = FramedRead::new(engine.stdout.take().unwrap(), UciCodec::new());
+///
+/// to_engine.send(UciMessage::Uci).await?;
+/// while let Some(msg) = from_engine.next().await {
+///     if msg? == UciMessage::UciOk {
+///         break;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct UciCodec {
+    config: ParserConfig,
+    line_no: usize,
+    next_index: usize,
+    discarding: bool,
+    castling: CastlingTracker,
+}
+
+impl UciCodec {
+    /// Creates a codec that decodes the input with the default `ParserConfig`.
+    pub fn new() -> UciCodec {
+        UciCodec::with_config(ParserConfig::default())
+    }
+
+    /// Creates a codec that decodes the input according to `config`.
+    pub fn with_config(config: ParserConfig) -> UciCodec {
+        UciCodec {
+            config,
+            line_no: 0,
+            next_index: 0,
+            discarding: false,
+            castling: CastlingTracker::default(),
+        }
+    }
+
+    /// Creates a codec with the default `ParserConfig`, except for the maximum line length, which is set to
+    /// `max_line_length` bytes.
+    pub fn with_max_line_length(max_line_length: usize) -> UciCodec {
+        UciCodec::with_config(ParserConfig::default().max_line_length(max_line_length))
+    }
+
+    /// Returns the maximum line length, in bytes.
+    pub fn max_line_length(&self) -> usize {
+        self.config.max_line_length
+    }
+}
+
+impl Default for UciCodec {
+    fn default() -> Self {
+        UciCodec::new()
+    }
+}
+
+impl Decoder for UciCodec {
+    type Item = UciMessage;
+    type Error = UciReadError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<UciMessage>, UciReadError> {
+        loop {
+            let newline = buf[self.next_index..].iter().position(|b| *b == b'\n');
+
+            let result = match newline {
+                Some(pos) => {
+                    let line = buf.split_to(self.next_index + pos + 1);
+                    self.next_index = 0;
+
+                    if self.discarding {
+                        // The end of a line that has already been reported as too long.
+                        self.discarding = false;
+                        continue;
+                    }
+
+                    self.line_no += 1;
+                    parse_byte_line(&line[..line.len() - 1], self.line_no, &self.config, &mut self.castling)
+                }
+                // One extra byte for the `\r` of a `\r\n` line terminator.
+                None if buf.len() > self.config.max_line_length.saturating_add(1) => {
+                    let result = if self.discarding {
+                        None
+                    } else {
+                        self.line_no += 1;
+                        self.discarding = true;
+                        too_long_byte_line(buf, self.line_no, &self.config)
+                    };
+
+                    buf.advance(buf.len());
+                    self.next_index = 0;
+                    result
+                }
+                None => {
+                    if self.discarding {
+                        buf.advance(buf.len());
+                    }
+                    self.next_index = buf.len();
+
+                    return Ok(None);
+                }
+            };
+
+            match result {
+                Some(Ok(msg)) => return Ok(Some(msg)),
+                Some(Err(e)) => return Err(UciReadError::Parse(e)),
+                None => continue,
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<UciMessage>, UciReadError> {
+        if let Some(msg) = self.decode(buf)? {
+            return Ok(Some(msg));
+        }
+
+        // The last line may not be terminated by a newline.
+        let line = buf.split();
+        self.next_index = 0;
+        if line.is_empty() || self.discarding {
+            self.discarding = false;
+            return Ok(None);
+        }
+
+        self.line_no += 1;
+        match parse_byte_line(&line, self.line_no, &self.config, &mut self.castling) {
+            Some(result) => Ok(Some(result?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Encoder<UciMessage> for UciCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, msg: UciMessage, dst: &mut BytesMut) -> Result<(), std::io::Error> {
+        self.encode(ByteVecUciMessage::from(msg), dst)
+    }
+}
+
+impl Encoder<ByteVecUciMessage> for UciCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, msg: ByteVecUciMessage, dst: &mut BytesMut) -> Result<(), std::io::Error> {
+        dst.extend_from_slice(&msg.bytes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::io::AsyncReadExt;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    use crate::config::UnknownMessagePolicy;
+    use crate::error::UciParseErrorKind;
+    use crate::parser::parse_bytes;
+
+    use super::*;
+
+    fn decode_all(codec: &mut UciCodec, chunks: &[&[u8]]) -> Vec<Result<UciMessage, UciReadError>> {
+        let mut buf = BytesMut::new();
+        let mut results = vec![];
+
+        for chunk in chunks {
+            buf.extend_from_slice(chunk);
+            loop {
+                match codec.decode(&mut buf) {
+                    Ok(Some(msg)) => results.push(Ok(msg)),
+                    Ok(None) => break,
+                    Err(e) => results.push(Err(e)),
+                }
+            }
+        }
+        while let Some(r) = codec.decode_eof(&mut buf).transpose() {
+            results.push(r);
+        }
+
+        results
+    }
+
+    #[test]
+    fn test_decode_matches_parse_bytes() {
+        let input: &[u8] = b"uci\r\n\nid name Caf\xe9\nfrobnicate\ninfo depth 10 pv e2e4 e7e5\n\
+            bestmove e2e4 ponder e7e5";
+        let expected = parse_bytes(input);
+
+        for size in 1..=input.len() {
+            let chunks: Vec<&[u8]> = input.chunks(size).collect();
+            let decoded: Vec<UciMessage> =
+                decode_all(&mut UciCodec::new(), &chunks).into_iter().map(Result::unwrap).collect();
+            assert_eq!(decoded, expected);
+        }
+    }
+
+    #[test]
+    fn test_decode_max_line_length() {
+        let mut codec = UciCodec::with_max_line_length(8);
+        assert_eq!(codec.max_line_length(), 8);
+
+        let results = decode_all(&mut codec, &[b"isready\r\ninfo string", b" way too long", b" really\nuci\nreadyok"]);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap(), &UciMessage::IsReady);
+        match results[1].as_ref().unwrap() {
+            UciMessage::Unknown(text, Some(e)) => {
+                assert_eq!(text, "info str");
+                assert_eq!((e.kind, e.line), (UciParseErrorKind::LineTooLong, 2));
+            }
+            m => panic!("Expected UciMessage::Unknown, got {:?}", m),
+        }
+        assert_eq!(results[2].as_ref().unwrap(), &UciMessage::Uci);
+        assert_eq!(results[3].as_ref().unwrap(), &UciMessage::ReadyOk);
+
+        let config = ParserConfig::default().unknown_messages(UnknownMessagePolicy::Error).max_line_length(8);
+        let mut codec = UciCodec::with_config(config);
+        let results = decode_all(&mut codec, &[b"info string way too long\nfoo\nuci\n"]);
+        assert!(matches!(&results[0], Err(UciReadError::Parse(e)) if e.kind == UciParseErrorKind::LineTooLong));
+        assert!(matches!(&results[1], Err(UciReadError::Parse(e)) if e.kind == UciParseErrorKind::UnknownCommand));
+        assert_eq!(results[2].as_ref().unwrap(), &UciMessage::Uci);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_framed() {
+        let (client, server) = tokio::io::duplex(64);
+        let (mut server_read, _server_write) = tokio::io::split(server);
+
+        let mut writer = FramedWrite::new(client, UciCodec::new());
+        writer.send(UciMessage::Uci).await.unwrap();
+        writer.send(UciMessage::go_infinite()).await.unwrap();
+        writer.send(ByteVecUciMessage::from(UciMessage::Stop)).await.unwrap();
+        drop(writer);
+
+        let mut sent = String::new();
+        server_read.read_to_string(&mut sent).await.unwrap();
+        assert_eq!(sent, "uci\ngo infinite \nstop\n");
+
+        let messages: Vec<UciMessage> =
+            FramedRead::new(sent.as_bytes(), UciCodec::new()).map(Result::unwrap).collect().await;
+        assert_eq!(messages, vec![UciMessage::Uci, UciMessage::go_infinite(), UciMessage::Stop]);
+    }
+}
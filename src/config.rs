@@ -0,0 +1,198 @@
+//! The `config` module contains the `ParserConfig` struct, used with `parse_with_config` to control how strictly the
+//! UCI input is parsed.
+
+use crate::chess960::CastlingMode;
+use crate::uci::CommunicationDirection;
+
+/// The default value of `ParserConfig::max_line_length`.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 64 * 1024;
+
+/// Specifies what happens to a message that cannot be parsed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub enum UnknownMessagePolicy {
+    /// The message is skipped, as with `parse`.
+    Ignore,
+
+    /// The message is wrapped in a `UciMessage::Unknown` variant, as with `parse_with_unknown`.
+    #[default]
+    Keep,
+
+    /// Parsing stops and the error is returned, as with `parse_strict`.
+    Error,
+}
+
+/// Configures the behaviour of `parse_with_config`. Each setting can be changed independently of the others; the
+/// `Default` implementation behaves like `parse_with_unknown`.
+///
+/// The struct is `#[non_exhaustive]`, so that new settings can be added without breaking changes. Start from
+/// `ParserConfig::default()`, `lenient()` or `strict()` and change the settings with the builder-style setters.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{parse_with_config, ParserConfig, UnknownMessagePolicy};
+///
+/// let config = ParserConfig::default()
+///     .unknown_messages(UnknownMessagePolicy::Error)
+///     .skip_unknown_go_tokens(true);
+///
+/// let messages = parse_with_config("go depth 10 frobnicate\n", &config).unwrap();
+/// assert_eq!(messages.len(), 1);
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct ParserConfig {
+    /// What to do with messages that cannot be parsed. Defaults to `UnknownMessagePolicy::Keep`.
+    pub unknown_messages: UnknownMessagePolicy,
+
+    /// If `true`, keywords must be lowercase, as in the UCI specification (`go`, but not `GO` or `Go`). Free text,
+    /// such as option names and values, FENs or `info string`, is not affected. Defaults to `false`.
+    pub case_sensitive: bool,
+
+    /// If `true`, lines may be terminated by `\r\n` as well as `\n`. Defaults to `true`.
+    pub allow_crlf: bool,
+
+    /// If `true`, unrecognised tokens in a `go` message are skipped, as the UCI specification recommends, instead of
+    /// rejecting the whole message. Defaults to `false`.
+    pub skip_unknown_go_tokens: bool,
+
+    /// If `true`, anything that follows a complete message on the same line is ignored instead of rejecting the
+    /// message. Defaults to `false`.
+    pub allow_trailing_garbage: bool,
+
+    /// If `true`, vendor-specific `info` attributes are kept as `UciInfoAttribute::Any`, otherwise they are dropped.
+    /// Defaults to `true`.
+    pub keep_unknown_info_attributes: bool,
+
+    /// If set, castling moves are converted into this notation, like with `parse_with_castling_mode`: the moves of
+    /// `position` messages and the moves of the messages that follow a `position` message (`bestmove`, `info pv`...).
+    /// Defaults to `None`.
+    pub castling_mode: Option<CastlingMode>,
+
+    /// If set, only the messages travelling in this direction are recognised, and messages travelling in the other
+    /// direction result in a `UciParseErrorKind::WrongDirection` error. For example, an engine reading its input
+    /// would set this to `CommunicationDirection::GuiToEngine`. Defaults to `None` (both directions).
+    pub direction: Option<CommunicationDirection>,
+
+    /// If `true`, `info`, `bestmove` and `position` messages are parsed by a specialised hand-written parser whenever
+    /// possible, which produces exactly the same messages as the PEG parser, only faster. Defaults to `true`.
+    pub fast_path: bool,
+
+    /// The maximum length of a line in bytes, without the line terminator. Longer lines result in a
+    /// `UciParseErrorKind::LineTooLong` error, and `UciStreamParser` only buffers this many bytes of a line. Defaults
+    /// to `DEFAULT_MAX_LINE_LENGTH` (64 KiB).
+    pub max_line_length: usize,
+}
+
+impl ParserConfig {
+    /// A lenient configuration, suitable for an engine talking to an arbitrary GUI: unrecognised messages are
+    /// ignored, and keyword case, unknown `go` tokens and trailing garbage are all tolerated.
+    pub fn lenient() -> ParserConfig {
+        ParserConfig {
+            unknown_messages: UnknownMessagePolicy::Ignore,
+            case_sensitive: false,
+            allow_crlf: true,
+            skip_unknown_go_tokens: true,
+            allow_trailing_garbage: true,
+            keep_unknown_info_attributes: true,
+            castling_mode: None,
+            direction: None,
+            fast_path: true,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+        }
+    }
+
+    /// A strict configuration, suitable for checking that the input follows the UCI specification to the letter:
+    /// the first problem is reported as an error.
+    pub fn strict() -> ParserConfig {
+        ParserConfig {
+            unknown_messages: UnknownMessagePolicy::Error,
+            case_sensitive: true,
+            allow_crlf: false,
+            skip_unknown_go_tokens: false,
+            allow_trailing_garbage: false,
+            keep_unknown_info_attributes: true,
+            castling_mode: None,
+            direction: None,
+            fast_path: true,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+        }
+    }
+
+    /// Sets `unknown_messages`, what to do with messages that cannot be parsed.
+    pub fn unknown_messages(mut self, unknown_messages: UnknownMessagePolicy) -> ParserConfig {
+        self.unknown_messages = unknown_messages;
+        self
+    }
+
+    /// Sets `case_sensitive`, whether keywords must be lowercase.
+    pub fn case_sensitive(mut self, case_sensitive: bool) -> ParserConfig {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Sets `allow_crlf`, whether lines may be terminated by `\r\n`.
+    pub fn allow_crlf(mut self, allow_crlf: bool) -> ParserConfig {
+        self.allow_crlf = allow_crlf;
+        self
+    }
+
+    /// Sets `skip_unknown_go_tokens`, whether unrecognised tokens in a `go` message are skipped.
+    pub fn skip_unknown_go_tokens(mut self, skip_unknown_go_tokens: bool) -> ParserConfig {
+        self.skip_unknown_go_tokens = skip_unknown_go_tokens;
+        self
+    }
+
+    /// Sets `allow_trailing_garbage`, whether anything after a complete message on the same line is ignored.
+    pub fn allow_trailing_garbage(mut self, allow_trailing_garbage: bool) -> ParserConfig {
+        self.allow_trailing_garbage = allow_trailing_garbage;
+        self
+    }
+
+    /// Sets `keep_unknown_info_attributes`, whether vendor-specific `info` attributes are kept.
+    pub fn keep_unknown_info_attributes(mut self, keep_unknown_info_attributes: bool) -> ParserConfig {
+        self.keep_unknown_info_attributes = keep_unknown_info_attributes;
+        self
+    }
+
+    /// Sets `castling_mode`, the notation castling moves are converted into, if any.
+    pub fn castling_mode(mut self, castling_mode: Option<CastlingMode>) -> ParserConfig {
+        self.castling_mode = castling_mode;
+        self
+    }
+
+    /// Sets `direction`, the direction of the messages that are recognised, if restricted.
+    pub fn direction(mut self, direction: Option<CommunicationDirection>) -> ParserConfig {
+        self.direction = direction;
+        self
+    }
+
+    /// Sets `fast_path`, whether the hand-written parser is used for `info`, `bestmove` and `position` messages.
+    pub fn fast_path(mut self, fast_path: bool) -> ParserConfig {
+        self.fast_path = fast_path;
+        self
+    }
+
+    /// Sets `max_line_length`, the maximum length of a line in bytes.
+    pub fn max_line_length(mut self, max_line_length: usize) -> ParserConfig {
+        self.max_line_length = max_line_length;
+        self
+    }
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        ParserConfig {
+            unknown_messages: UnknownMessagePolicy::default(),
+            case_sensitive: false,
+            allow_crlf: true,
+            skip_unknown_go_tokens: false,
+            allow_trailing_garbage: false,
+            keep_unknown_info_attributes: true,
+            castling_mode: None,
+            direction: None,
+            fast_path: true,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! The `duration` module contains `UciDuration`, the signed millisecond time type used for the clock values of the
+//! `go` message and for `info time`.
+
+use core::convert::TryFrom;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::time::Duration;
+
+/// A span of time with millisecond precision, as used by the UCI protocol. Unlike `std::time::Duration`, it can be
+/// negative, because GUIs legitimately send negative clock values (such as `wtime -50`) once a player has overstepped
+/// the time.
+///
+/// `UciDuration` converts losslessly into a (non-negative) `std::time::Duration` with `TryFrom`, and, with the
+/// `chrono` feature, from and into a `chrono::Duration` with `From`.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use std::time::Duration;
+/// use vampirc_uci::UciDuration;
+///
+/// let wtime = UciDuration::milliseconds(1500);
+/// assert_eq!(Duration::try_from(wtime), Ok(Duration::from_millis(1500)));
+///
+/// let overstepped = UciDuration::milliseconds(-50);
+/// assert!(overstepped.is_negative());
+/// assert!(Duration::try_from(overstepped).is_err());
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct UciDuration(i64);
+
+impl UciDuration {
+    /// The zero duration.
+    pub const ZERO: UciDuration = UciDuration(0);
+
+    /// Creates a `UciDuration` of the specified number of milliseconds.
+    pub const fn milliseconds(milliseconds: i64) -> UciDuration {
+        UciDuration(milliseconds)
+    }
+
+    /// Creates a `UciDuration` of the specified number of seconds, saturating at the bounds of the type.
+    pub const fn seconds(seconds: i64) -> UciDuration {
+        UciDuration(seconds.saturating_mul(1000))
+    }
+
+    /// Returns the number of milliseconds in this duration.
+    pub const fn num_milliseconds(&self) -> i64 {
+        self.0
+    }
+
+    /// Returns the number of whole seconds in this duration, rounded towards zero.
+    pub const fn num_seconds(&self) -> i64 {
+        self.0 / 1000
+    }
+
+    /// Returns `true` if this duration is negative.
+    pub const fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+}
+
+impl Display for UciDuration {
+    /// Formats the duration as the number of milliseconds, as in the UCI protocol.
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The error returned when a duration cannot be converted, because it is negative or too large for the target type.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct DurationOutOfRangeError;
+
+impl Display for DurationOutOfRangeError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "duration out of range")
+    }
+}
+
+impl core::error::Error for DurationOutOfRangeError {}
+
+impl TryFrom<UciDuration> for Duration {
+    type Error = DurationOutOfRangeError;
+
+    /// Converts a non-negative `UciDuration` into a `std::time::Duration`. Fails if the duration is negative.
+    fn try_from(d: UciDuration) -> Result<Duration, DurationOutOfRangeError> {
+        u64::try_from(d.0).map(Duration::from_millis).map_err(|_| DurationOutOfRangeError)
+    }
+}
+
+impl TryFrom<Duration> for UciDuration {
+    type Error = DurationOutOfRangeError;
+
+    /// Converts a `std::time::Duration` into a `UciDuration`, truncating it to whole milliseconds. Fails if the
+    /// duration does not fit into an `i64` number of milliseconds.
+    fn try_from(d: Duration) -> Result<UciDuration, DurationOutOfRangeError> {
+        i64::try_from(d.as_millis()).map(UciDuration).map_err(|_| DurationOutOfRangeError)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<UciDuration> for chrono::Duration {
+    /// Converts a `UciDuration` into a `chrono::Duration`. The only value that `chrono::Duration` cannot represent,
+    /// `i64::MIN` milliseconds, is converted into `chrono::Duration::MIN`.
+    fn from(d: UciDuration) -> chrono::Duration {
+        chrono::Duration::try_milliseconds(d.0).unwrap_or(chrono::Duration::MIN)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::Duration> for UciDuration {
+    /// Converts a `chrono::Duration` into a `UciDuration`, truncating it to whole milliseconds.
+    fn from(d: chrono::Duration) -> UciDuration {
+        UciDuration(d.num_milliseconds())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_std_conversions() {
+        assert_eq!(Duration::try_from(UciDuration::milliseconds(0)), Ok(Duration::ZERO));
+        assert_eq!(Duration::try_from(UciDuration::seconds(90)), Ok(Duration::from_secs(90)));
+        assert_eq!(Duration::try_from(UciDuration::milliseconds(-1)), Err(DurationOutOfRangeError));
+
+        assert_eq!(UciDuration::try_from(Duration::from_micros(1999)), Ok(UciDuration::milliseconds(1)));
+        assert_eq!(UciDuration::try_from(Duration::MAX), Err(DurationOutOfRangeError));
+
+        let max = UciDuration::milliseconds(i64::MAX);
+        assert_eq!(UciDuration::try_from(Duration::try_from(max).unwrap()), Ok(max));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let d = UciDuration::milliseconds(-4061);
+        assert_eq!((d.num_milliseconds(), d.num_seconds(), d.is_negative()), (-4061, -4, true));
+        assert_eq!(d.to_string(), "-4061");
+        assert!(d < UciDuration::ZERO);
+        assert_eq!(UciDuration::seconds(i64::MAX), UciDuration::milliseconds(i64::MAX));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_conversions() {
+        for ms in [0, -50, 1500, i64::MAX, -i64::MAX].iter() {
+            let d = UciDuration::milliseconds(*ms);
+            let c = chrono::Duration::from(d);
+            assert_eq!(c.num_milliseconds(), *ms);
+            assert_eq!(UciDuration::from(c), d);
+        }
+
+        assert_eq!(chrono::Duration::from(UciDuration::milliseconds(i64::MIN)), chrono::Duration::MIN);
+        assert_eq!(UciDuration::from(chrono::Duration::microseconds(-1999)), UciDuration::milliseconds(-1));
+    }
+}
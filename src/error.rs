@@ -0,0 +1,373 @@
+//! The `error` module contains the `UciParseError` type, which describes why (and where) a UCI message could not be
+//! parsed, and the `UciReadError` type returned by `UciReader`.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error as StdError;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+#[cfg(feature = "std")]
+use std::io;
+
+use pest::error::{Error as PestError, ErrorVariant, InputLocation};
+
+use crate::parser::Rule;
+
+/// The category of a `UciParseError`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum UciParseErrorKind {
+    /// The input does not start with a recognised UCI command.
+    UnknownCommand,
+
+    /// A move (or a square within a move) is malformed.
+    BadMove,
+
+    /// A number is well-formed, but does not fit into the type used to represent it.
+    OutOfRange,
+
+    /// The FEN string in a `position` message is malformed.
+    BadFen,
+
+    /// The command was recognised, but the rest of the message does not follow the UCI syntax.
+    Syntax,
+
+    /// The message is valid, but is sent in the other direction (for example, `bestmove` sent to an engine).
+    WrongDirection,
+
+    /// The line is longer than the configured maximum line length (see `ParserConfig::max_line_length`).
+    LineTooLong,
+}
+
+impl Display for UciParseErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let s = match self {
+            UciParseErrorKind::UnknownCommand => "unknown command",
+            UciParseErrorKind::BadMove => "bad move",
+            UciParseErrorKind::OutOfRange => "number out of range",
+            UciParseErrorKind::BadFen => "bad FEN",
+            UciParseErrorKind::Syntax => "syntax error",
+            UciParseErrorKind::WrongDirection => "message sent in the wrong direction",
+            UciParseErrorKind::LineTooLong => "line too long",
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+/// An error encountered while parsing UCI input. The `line` and `column` are 1-based and point to the start of the
+/// offending `text`.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UciParseError {
+    /// The category of the error.
+    pub kind: UciParseErrorKind,
+
+    /// The line (starting with 1) on which the error occurred.
+    pub line: usize,
+
+    /// The column (starting with 1) at which the error occurred.
+    pub column: usize,
+
+    /// The offending part of the input. Empty if the input ended unexpectedly.
+    pub text: String,
+
+    /// A human-readable list of what the parser expected to find at this position. May be empty.
+    pub expected: Vec<String>,
+}
+
+impl UciParseError {
+    /// Constructs a new `UciParseError`.
+    pub fn new(kind: UciParseErrorKind, line: usize, column: usize, text: &str, expected: Vec<String>) -> UciParseError {
+        UciParseError {
+            kind,
+            line,
+            column,
+            text: text.to_string(),
+            expected,
+        }
+    }
+
+    /// Converts a grammar error produced by the parser for `input` into a `UciParseError`.
+    pub(crate) fn from_pest(e: PestError<Rule>, input: &str) -> UciParseError {
+        let pos = match e.location {
+            InputLocation::Pos(p) => p,
+            InputLocation::Span((p, _)) => p,
+        };
+        let rest = input.get(pos..).unwrap_or("");
+        let text = rest.split(char::is_whitespace).next().unwrap_or("");
+        let (line, column) = line_col(input, pos);
+
+        let positives = match e.variant {
+            ErrorVariant::ParsingError { positives, .. } => positives,
+            ErrorVariant::CustomError { .. } => vec![],
+        };
+
+        let before = input.get(..pos).unwrap_or("");
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let kind = if positives.iter().any(|r| is_fen_rule(*r)) {
+            UciParseErrorKind::BadFen
+        } else if positives.iter().any(|r| is_move_rule(*r)) {
+            UciParseErrorKind::BadMove
+        } else if before[line_start..].trim().is_empty() {
+            UciParseErrorKind::UnknownCommand
+        } else {
+            UciParseErrorKind::Syntax
+        };
+
+        let mut expected: Vec<String> = vec![];
+        for r in positives {
+            let name = describe_rule(r);
+            if !expected.contains(&name) {
+                expected.push(name);
+            }
+        }
+
+        UciParseError::new(kind, line, column, text, expected)
+    }
+}
+
+impl Display for UciParseError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{} at line {}, column {}", self.kind, self.line, self.column)?;
+
+        if !self.text.is_empty() {
+            write!(f, ": `{}`", self.text)?;
+        }
+
+        if !self.expected.is_empty() {
+            write!(f, " (expected {})", self.expected.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl StdError for UciParseError {}
+
+/// An error encountered by `UciReader`: either the underlying reader failed, or a line could not be parsed.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum UciReadError {
+    /// Reading from the underlying reader failed.
+    Io(io::Error),
+
+    /// A line could not be parsed.
+    Parse(UciParseError),
+}
+
+#[cfg(feature = "std")]
+impl Display for UciReadError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            UciReadError::Io(e) => write!(f, "I/O error: {}", e),
+            UciReadError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl StdError for UciReadError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            UciReadError::Io(e) => Some(e),
+            UciReadError::Parse(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for UciReadError {
+    fn from(e: io::Error) -> Self {
+        UciReadError::Io(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<UciParseError> for UciReadError {
+    fn from(e: UciParseError) -> Self {
+        UciReadError::Parse(e)
+    }
+}
+
+/// Returns the 1-based line and column of the byte position `pos` in `input`.
+pub(crate) fn line_col(input: &str, pos: usize) -> (usize, usize) {
+    let before = input.get(..pos).unwrap_or(input);
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+
+    (line, column)
+}
+
+fn is_fen_rule(rule: Rule) -> bool {
+    matches!(
+        rule,
+        Rule::fen
+            | Rule::fen_rank
+            | Rule::piece_char
+            | Rule::color
+            | Rule::castling
+            | Rule::castling_right
+            | Rule::en_passant
+            | Rule::fen_none
+            | Rule::ply_clock
+            | Rule::move_num
+    )
+}
+
+fn is_move_rule(rule: Rule) -> bool {
+    matches!(
+        rule,
+        Rule::a_move
+            | Rule::null_move
+            | Rule::from_sq
+            | Rule::to_sq
+            | Rule::square
+            | Rule::file
+            | Rule::rank
+            | Rule::promotion
+    )
+}
+
+/// Returns a human-readable description of what `rule` matches, for the `expected` list of a `UciParseError`.
+/// Keywords are described by themselves, anything else by a short noun phrase.
+fn describe_rule(rule: Rule) -> String {
+    let description = match rule {
+        Rule::EOI => "end of input",
+
+        Rule::uci => "uci",
+        Rule::debug => "debug",
+        Rule::switch => "on or off",
+        Rule::isready => "isready",
+        Rule::setoption => "setoption",
+        Rule::option_internal | Rule::option_name | Rule::option_name2 => "an option name",
+        Rule::option_value => "an option value",
+        Rule::register => "register",
+        Rule::register_later => "later",
+        Rule::register_nc => "name and code",
+        Rule::register_name => "a registration name",
+        Rule::register_code => "a registration code",
+        Rule::ucinewgame => "ucinewgame",
+        Rule::stop => "stop",
+        Rule::quit => "quit",
+        Rule::ponderhit => "ponderhit",
+
+        Rule::position => "position",
+        Rule::startpos => "startpos",
+        Rule::a_move => "a move",
+        Rule::null_move => "the null move",
+        Rule::square => "a square",
+        Rule::from_sq => "a source square",
+        Rule::to_sq => "a destination square",
+        Rule::file => "a file",
+        Rule::rank => "a rank",
+        Rule::promotion => "a promotion piece",
+
+        Rule::fen => "a FEN string",
+        Rule::fen_rank => "a FEN rank",
+        Rule::piece_char => "a piece",
+        Rule::color => "the side to move",
+        Rule::fen_none => "-",
+        Rule::castling | Rule::castling_chars => "castling rights",
+        Rule::castling_right => "a castling right",
+        Rule::en_passant => "an en passant square",
+        Rule::ply_clock => "a halfmove clock",
+        Rule::move_num => "a move number",
+
+        Rule::go => "go",
+        Rule::go_empty | Rule::go_full | Rule::go_unknown => "a go parameter",
+        Rule::go_time | Rule::go_timeleft => "a time control",
+        Rule::go_search => "a search control",
+        Rule::go_ponder => "ponder",
+        Rule::go_infinite => "infinite",
+        Rule::go_movetime => "movetime",
+        Rule::wtime => "wtime",
+        Rule::btime => "btime",
+        Rule::winc => "winc",
+        Rule::binc => "binc",
+        Rule::movestogo => "movestogo",
+        Rule::depth | Rule::info_depth => "depth",
+        Rule::nodes | Rule::info_nodes => "nodes",
+        Rule::mate | Rule::info_mate => "mate",
+        Rule::searchmoves => "searchmoves",
+
+        Rule::id => "id",
+        Rule::id_name => "name",
+        Rule::id_author => "author",
+        Rule::id_text => "an id value",
+        Rule::uciok => "uciok",
+        Rule::readyok => "readyok",
+        Rule::bestmove => "bestmove",
+        Rule::bestmove_ponder => "ponder",
+        Rule::copyprotection => "copyprotection",
+        Rule::registration => "registration",
+        Rule::protection_checking => "checking",
+        Rule::protection_ok => "ok",
+        Rule::protection_error => "error",
+
+        Rule::option => "option",
+        Rule::option_type => "an option type",
+        Rule::option_check => "check",
+        Rule::option_spin => "spin",
+        Rule::option_combo => "combo",
+        Rule::option_string | Rule::info_string => "string",
+        Rule::option_button => "button",
+        Rule::option_default => "default",
+        Rule::option_min => "min",
+        Rule::option_max => "max",
+        Rule::option_var => "var",
+
+        Rule::info => "info",
+        Rule::info_attribute | Rule::info_any | Rule::info_keyword => "an info attribute",
+        Rule::info_any_value => "an info attribute value",
+        Rule::info_string_string => "a string",
+        Rule::info_seldepth => "seldepth",
+        Rule::info_time => "time",
+        Rule::info_currmove => "currmove",
+        Rule::info_currmovenum => "currmovenumber",
+        Rule::info_hashfull => "hashfull",
+        Rule::info_nps => "nps",
+        Rule::info_tbhits => "tbhits",
+        Rule::info_sbhits => "sbhits",
+        Rule::info_cpuload => "cpuload",
+        Rule::info_pv => "pv",
+        Rule::info_multipv => "multipv",
+        Rule::info_refutation => "refutation",
+        Rule::info_currline => "currline",
+        Rule::info_cpunr => "a CPU number",
+        Rule::info_score => "score",
+        Rule::info_cp => "cp",
+        Rule::info_lowerbound => "lowerbound",
+        Rule::info_upperbound => "upperbound",
+        Rule::info_wdl => "wdl",
+
+        Rule::digits | Rule::i64 | Rule::milliseconds => "a number",
+        Rule::digit => "a digit",
+        Rule::alpha => "a letter",
+        Rule::token => "a word",
+        Rule::something_produced | Rule::something_produced_nl => "a message",
+
+        // Silent rules are never reported by pest, but are part of `Rule` all the same.
+        Rule::commands
+        | Rule::commands_ignore_unknown
+        | Rule::commands_with_unknown
+        | Rule::message
+        | Rule::message_no_nl
+        | Rule::single_message_per_line
+        | Rule::engine_bound_message_no_nl
+        | Rule::gui_bound_message_no_nl
+        | Rule::single_engine_bound_message
+        | Rule::single_gui_bound_message
+        | Rule::something => "a message",
+        Rule::option_token | Rule::register_token | Rule::info_any_token | Rule::non_ws | Rule::any => "a word",
+        Rule::go_keyword => "a go parameter",
+        Rule::fen_pos => "fen",
+        Rule::rank_sep => "/",
+        Rule::counter => "a number",
+        Rule::sign | Rule::PLUS | Rule::MINUS => "a sign",
+        Rule::WHITESPACE | Rule::ws_nl => "whitespace",
+    };
+
+    description.to_string()
+}
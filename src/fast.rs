@@ -0,0 +1,294 @@
+//! A hand-written parser for the most frequent messages (`info`, `bestmove` and `position`), which lets them skip the
+//! general-purpose PEG parser. It only accepts input that it parses exactly like the grammar in `res/uci.pest` (plus
+//! the conversion in the `parser` module) would, and returns `None` for anything else, in which case the caller falls
+//! back to the PEG parser. Keywords must therefore be lowercase, and unknown `info` attributes, malformed or
+//! out-of-range values and anything following a complete message all result in `None`.
+
+use alloc::vec;
+use core::str::FromStr;
+
+use crate::borrowed::{parse_move_str, UciInfoAttributeRef, UciMessageRef, UciMovesRef};
+use crate::duration::UciDuration;
+use crate::uci::Move;
+
+/// Parses a single line, optionally terminated by a newline, if it is an `info`, `bestmove` or `position` message
+/// that the fast path can handle. If `lowercase_moves` is `true`, moves containing uppercase letters are left to the
+/// PEG parser, so that a case-sensitive parse can report them.
+pub(crate) fn parse_line<'a>(s: &'a str, lowercase_moves: bool) -> Option<UciMessageRef<'a>> {
+    let line = s
+        .strip_suffix("\r\n")
+        .or_else(|| s.strip_suffix('\n'))
+        .or_else(|| s.strip_suffix('\r'))
+        .unwrap_or(s);
+
+    if line.contains(['\r', '\n']) {
+        return None;
+    }
+
+    let mut tokens = Tokens::new(line);
+    let msg = match tokens.next()? {
+        "info" => parse_info(&mut tokens, lowercase_moves)?,
+        "bestmove" => parse_bestmove(&mut tokens, lowercase_moves)?,
+        "position" => parse_position(&mut tokens, lowercase_moves)?,
+        _ => return None,
+    };
+
+    // The whole line must have been consumed.
+    match tokens.next() {
+        Some(_) => None,
+        None => Some(msg),
+    }
+}
+
+/// Splits a line into tokens separated by spaces and tabs, keeping track of their positions.
+#[derive(Copy, Clone)]
+struct Tokens<'a> {
+    line: &'a str,
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(line: &'a str) -> Tokens<'a> {
+        Tokens { line, pos: 0 }
+    }
+
+    /// Returns the number of whitespace characters at the current position.
+    fn whitespace_len(&self) -> usize {
+        self.line[self.pos..].bytes().take_while(|b| is_whitespace(*b)).count()
+    }
+
+    /// Returns the next token along with its starting position.
+    fn next_with_start(&mut self) -> Option<(usize, &'a str)> {
+        let start = self.pos + self.whitespace_len();
+        let len = self.line[start..].bytes().take_while(|b| !is_whitespace(*b)).count();
+        self.pos = start + len;
+
+        if len == 0 {
+            None
+        } else {
+            Some((start, &self.line[start..self.pos]))
+        }
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.next_with_start().map(|(_, t)| t)
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        let mut copy = *self;
+        copy.next()
+    }
+
+    /// Consumes and returns the rest of the line, without the leading whitespace.
+    fn rest(&mut self) -> &'a str {
+        let rest = &self.line[self.pos + self.whitespace_len()..];
+        self.pos = self.line.len();
+
+        rest
+    }
+}
+
+fn is_whitespace(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+fn parse_info<'a>(t: &mut Tokens<'a>, lowercase_moves: bool) -> Option<UciMessageRef<'a>> {
+    let mut attributes = vec![];
+
+    while let Some(keyword) = t.next() {
+        let attribute = match keyword {
+            "depth" => UciInfoAttributeRef::Depth(number(t.next()?, 5)?),
+            "seldepth" => UciInfoAttributeRef::SelDepth(number(t.next()?, 5)?),
+            "time" => UciInfoAttributeRef::Time(UciDuration::milliseconds(number(t.next()?, 12)?)),
+            "nodes" => UciInfoAttributeRef::Nodes(number(t.next()?, 12)?),
+            "currmovenumber" | "currmovenum" => UciInfoAttributeRef::CurrMoveNum(number(t.next()?, 12)?),
+            "currmove" => UciInfoAttributeRef::CurrMove(a_move(t.next()?, lowercase_moves)?),
+            "hashfull" => UciInfoAttributeRef::HashFull(number(t.next()?, 12)?),
+            "nps" => UciInfoAttributeRef::Nps(number(t.next()?, 12)?),
+            "tbhits" => UciInfoAttributeRef::TbHits(number(t.next()?, 12)?),
+            "sbhits" => UciInfoAttributeRef::SbHits(number(t.next()?, 12)?),
+            "cpuload" => UciInfoAttributeRef::CpuLoad(number(t.next()?, 12)?),
+            "multipv" => UciInfoAttributeRef::MultiPv(number(t.next()?, 12)?),
+            "pv" => UciInfoAttributeRef::Pv(moves(t, lowercase_moves)?),
+            "refutation" => UciInfoAttributeRef::Refutation(moves(t, lowercase_moves)?),
+            "currline" => {
+                let mut cpu_nr = None;
+                while let Some(n) = t.peek().and_then(|n| number(n, 3)) {
+                    cpu_nr = Some(n);
+                    t.next();
+                }
+
+                UciInfoAttributeRef::CurrLine {
+                    cpu_nr,
+                    line: moves(t, lowercase_moves)?,
+                }
+            }
+            "score" => {
+                let (cp, mate) = match t.next()? {
+                    "cp" => (Some(signed_number(t.next()?)?), None),
+                    "mate" => (None, Some(signed_number(t.next()?)?)),
+                    _ => return None,
+                };
+
+                let mut lower_bound = None;
+                let mut upper_bound = None;
+                loop {
+                    match t.peek() {
+                        Some("lowerbound") => lower_bound = Some(true),
+                        Some("upperbound") => upper_bound = Some(true),
+                        _ => break,
+                    }
+                    t.next();
+                }
+
+                UciInfoAttributeRef::Score {
+                    cp,
+                    mate,
+                    lower_bound,
+                    upper_bound,
+                }
+            }
+            "wdl" => UciInfoAttributeRef::Wdl {
+                win: number(t.next()?, 12)?,
+                draw: number(t.next()?, 12)?,
+                loss: number(t.next()?, 12)?,
+            },
+            "string" => {
+                // The string extends to the end of the line, including any trailing whitespace.
+                let s = t.rest();
+                if s.is_empty() {
+                    return None;
+                }
+
+                UciInfoAttributeRef::String(s)
+            }
+            _ => return None,
+        };
+
+        attributes.push(attribute);
+    }
+
+    if attributes.is_empty() {
+        None
+    } else {
+        Some(UciMessageRef::Info(attributes))
+    }
+}
+
+fn parse_bestmove<'a>(t: &mut Tokens<'a>, lowercase_moves: bool) -> Option<UciMessageRef<'a>> {
+    let best_move = a_move(t.next()?, lowercase_moves)?;
+    let mut ponder = None;
+
+    while let Some(keyword) = t.next() {
+        if keyword != "ponder" {
+            return None;
+        }
+        ponder = Some(a_move(t.next()?, lowercase_moves)?);
+    }
+
+    Some(UciMessageRef::BestMove { best_move, ponder })
+}
+
+fn parse_position<'a>(t: &mut Tokens<'a>, lowercase_moves: bool) -> Option<UciMessageRef<'a>> {
+    let (startpos, fen) = match t.next()? {
+        "startpos" => (true, None),
+        "fen" => (false, Some(fen(t)?)),
+        _ => return None,
+    };
+
+    let mut move_list = UciMovesRef::default();
+    if t.peek().is_some() {
+        // The grammar requires exactly one whitespace character between `moves` and the first move.
+        if t.next()? != "moves" || t.whitespace_len() != 1 {
+            return None;
+        }
+        move_list = moves(t, lowercase_moves)?;
+    }
+
+    Some(UciMessageRef::Position {
+        startpos,
+        fen,
+        moves: move_list,
+    })
+}
+
+/// Consumes a FEN with optional move counters and returns it exactly as it appears in the line.
+fn fen<'a>(t: &mut Tokens<'a>) -> Option<&'a str> {
+    let (start, placement) = t.next_with_start()?;
+
+    let mut ranks = 0;
+    for rank in placement.split('/') {
+        ranks += 1;
+        if rank.is_empty() || rank.len() > 8 || !rank.bytes().all(|b| b"kqrnbpKQRNBP12345678".contains(&b)) {
+            return None;
+        }
+    }
+
+    let castling = |s: &str| s == "-" || (s.len() <= 4 && s.bytes().all(|b| b"KQkqABCDEFGHabcdefgh".contains(&b)));
+    let square = |s: &[u8]| matches!(s, [f, r] if (b'a'..=b'h').contains(&f.to_ascii_lowercase()) && (b'1'..=b'8').contains(r));
+
+    if ranks != 8
+        || !matches!(t.next()?, "w" | "b" | "W" | "B")
+        || !castling(t.next()?)
+        || !matches!(t.next()?, ep if ep == "-" || square(ep.as_bytes()))
+    {
+        return None;
+    }
+
+    // Up to two move counters of at most four digits each.
+    let mut end = t.pos;
+    for _ in 0..2 {
+        match t.peek() {
+            Some(c) if c.len() <= 4 && c.bytes().all(|b| b.is_ascii_digit()) => {
+                t.next();
+                end = t.pos;
+            }
+            _ => break,
+        }
+    }
+
+    Some(&t.line[start..end])
+}
+
+/// Consumes one or more moves, validating each of them, and returns them as a `UciMovesRef` borrowing the line.
+fn moves<'a>(t: &mut Tokens<'a>, lowercase_moves: bool) -> Option<UciMovesRef<'a>> {
+    let mut range: Option<(usize, usize)> = None;
+
+    while let Some(token) = t.peek() {
+        if a_move(token, false).is_none() {
+            break;
+        }
+        if lowercase_moves && token.bytes().any(|b| b.is_ascii_uppercase()) {
+            return None;
+        }
+
+        let (start, _) = t.next_with_start()?;
+        range = Some((range.map_or(start, |r| r.0), t.pos));
+    }
+
+    range.map(|(start, end)| UciMovesRef::new(&t.line[start..end]))
+}
+
+fn a_move(token: &str, lowercase_moves: bool) -> Option<Move> {
+    if lowercase_moves && token.bytes().any(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+
+    parse_move_str(token)
+}
+
+/// Parses an unsigned number of at most `max_digits` digits that fits into `T`.
+fn number<T: FromStr>(token: &str, max_digits: usize) -> Option<T> {
+    if token.is_empty() || token.len() > max_digits || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    token.parse().ok()
+}
+
+/// Parses a number with an optional minus sign and at most 12 digits that fits into `T`.
+fn signed_number<T: FromStr>(token: &str) -> Option<T> {
+    number::<u64>(token.strip_prefix('-').unwrap_or(token), 12)?;
+
+    token.parse().ok()
+}
@@ -0,0 +1,473 @@
+//! The `fen` module contains a structured view of a [FEN](https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation)
+//! string, obtainable from a `UciFen` with `UciFen::position()`. It lets code such as time management or logging read
+//! the side to move, the castling rights or the move counters without re-parsing the FEN or depending on a chess
+//! library.
+//!
+//! With the `chess` feature enabled, the `chess` crate's `Color`, `Piece` and `Square` are used in place of `UciColor`,
+//! `UciPiece` and `UciSquare`.
+
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::str::FromStr;
+
+#[cfg(feature = "chess")]
+use chess::{Color, File, Piece, Rank, Square};
+
+use crate::chess960::castling_rights_valid;
+use crate::error::{line_col, UciParseError, UciParseErrorKind};
+#[cfg(not(feature = "chess"))]
+use crate::uci::{UciColor as Color, UciPiece as Piece, UciSquare as Square};
+use crate::uci::UciFen;
+
+/// The side of the board a castling right refers to.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum CastlingSide {
+    /// Castling with the king-side rook (`K` or `k`).
+    KingSide,
+
+    /// Castling with the queen-side rook (`Q` or `q`).
+    QueenSide,
+
+    /// Castling with the rook on this file (`a` to `h`), as used by Shredder-FEN and X-FEN.
+    RookFile(char),
+}
+
+/// A single castling right from the castling field of a FEN.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct CastlingRight {
+    /// The side that may castle.
+    pub color: Color,
+
+    /// The rook the side may castle with.
+    pub side: CastlingSide,
+}
+
+/// A piece on the board, together with its colour.
+pub type ColoredPiece = (Color, Piece);
+
+/// The structured contents of a FEN string.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct FenPosition {
+    /// The piece placement, indexed as `board[rank][file]`, with `board[0][0]` being the `a1` square and
+    /// `board[7][7]` the `h8` square.
+    pub board: [[Option<ColoredPiece>; 8]; 8],
+
+    /// The side to move.
+    pub side_to_move: Color,
+
+    /// The castling rights, in the order they appear in the FEN. Empty if neither side may castle.
+    pub castling_rights: Vec<CastlingRight>,
+
+    /// The en passant target square, if any.
+    pub en_passant: Option<Square>,
+
+    /// The number of halfmoves since the last capture or pawn move. `0` if the FEN did not contain it.
+    pub halfmove_clock: u16,
+
+    /// The number of the full move, starting with 1. `1` if the FEN did not contain it.
+    pub fullmove_number: u16,
+}
+
+impl FenPosition {
+    /// Returns the piece on the square at the given file (`0` for `a`) and rank (`0` for rank 1) indices, if any.
+    pub fn piece_at(&self, file: usize, rank: usize) -> Option<ColoredPiece> {
+        self.board.get(rank).and_then(|r| r.get(file)).copied().flatten()
+    }
+
+    /// Returns `true` if the position is plausible: each side has exactly one king, there are no pawns on the first
+    /// or the last rank, the en passant square is on the correct rank for the side to move and the castling rights
+    /// are consistent with the piece placement.
+    pub fn is_valid(&self) -> bool {
+        let count = |piece: ColoredPiece| self.board.iter().flatten().filter(|p| **p == Some(piece)).count();
+
+        if count((Color::White, Piece::King)) != 1 || count((Color::Black, Piece::King)) != 1 {
+            return false;
+        }
+
+        let pawn_on_edge = self.board[0]
+            .iter()
+            .chain(self.board[7].iter())
+            .any(|p| matches!(p, Some((_, Piece::Pawn))));
+        if pawn_on_edge {
+            return false;
+        }
+
+        if let Some(ep) = self.en_passant {
+            let expected_rank = if self.side_to_move == Color::White { 5 } else { 2 };
+            if square_indices(&ep).1 != expected_rank {
+                return false;
+            }
+        }
+
+        castling_rights_valid(&self.to_string())
+    }
+}
+
+impl FromStr for FenPosition {
+    type Err = UciParseError;
+
+    /// Parses a FEN string. The halfmove clock and the fullmove number may be omitted, in which case they default to
+    /// `0` and `1`, respectively. An error of the `UciParseErrorKind::BadFen` kind is returned if the FEN is malformed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = split_fields(s);
+        let field = |index: usize, expected: &str| {
+            fields
+                .get(index)
+                .copied()
+                .ok_or_else(|| fen_error(s, s.trim_end().len(), "", expected))
+        };
+
+        let (offset, placement) = field(0, "piece placement")?;
+        let board = parse_board(s, offset, placement)?;
+
+        let (offset, color) = field(1, "side to move")?;
+        let side_to_move = match color {
+            "w" | "W" => Color::White,
+            "b" | "B" => Color::Black,
+            _ => return Err(fen_error(s, offset, color, "side to move")),
+        };
+
+        let (offset, castling) = field(2, "castling rights")?;
+        let castling_rights = parse_castling(s, offset, castling)?;
+
+        let (offset, ep) = field(3, "en passant square")?;
+        let en_passant = if ep == "-" {
+            None
+        } else {
+            Some(parse_square(ep).ok_or_else(|| fen_error(s, offset, ep, "en passant square"))?)
+        };
+
+        let counter = |index: usize, default: u16, expected: &str| match fields.get(index) {
+            Some((offset, text)) => text.parse::<u16>().map_err(|_| fen_error(s, *offset, text, expected)),
+            None => Ok(default),
+        };
+        let halfmove_clock = counter(4, 0, "halfmove clock")?;
+        let fullmove_number = counter(5, 1, "fullmove number")?;
+
+        if let Some((offset, text)) = fields.get(6) {
+            return Err(fen_error(s, *offset, text, "end of FEN"));
+        }
+
+        Ok(FenPosition {
+            board,
+            side_to_move,
+            castling_rights,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+        })
+    }
+}
+
+impl Display for FenPosition {
+    /// Outputs the position as a six-field FEN string.
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for square in self.board[rank].iter() {
+                match square {
+                    Some((color, piece)) => {
+                        if empty > 0 {
+                            write!(f, "{}", empty)?;
+                            empty = 0;
+                        }
+                        write!(f, "{}", piece_to_char(*color, *piece))?;
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                write!(f, "{}", empty)?;
+            }
+            if rank > 0 {
+                write!(f, "/")?;
+            }
+        }
+
+        write!(f, " {} ", if self.side_to_move == Color::White { 'w' } else { 'b' })?;
+
+        if self.castling_rights.is_empty() {
+            write!(f, "-")?;
+        }
+        for right in &self.castling_rights {
+            let c = match right.side {
+                CastlingSide::KingSide => 'k',
+                CastlingSide::QueenSide => 'q',
+                CastlingSide::RookFile(file) => file.to_ascii_lowercase(),
+            };
+            let c = if right.color == Color::White { c.to_ascii_uppercase() } else { c };
+            write!(f, "{}", c)?;
+        }
+
+        match self.en_passant {
+            Some(ep) => write!(f, " {}", ep)?,
+            None => write!(f, " -")?,
+        }
+
+        write!(f, " {} {}", self.halfmove_clock, self.fullmove_number)
+    }
+}
+
+impl From<&FenPosition> for UciFen {
+    /// Serializes the structured position into a six-field `UciFen`.
+    fn from(position: &FenPosition) -> Self {
+        UciFen(position.to_string())
+    }
+}
+
+/// Splits the FEN into its whitespace-separated fields, each paired with its byte offset.
+fn split_fields(s: &str) -> Vec<(usize, &str)> {
+    let mut fields = vec![];
+    let mut start: Option<usize> = None;
+
+    for (i, c) in s.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(st)) => {
+                fields.push((st, &s[st..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(st) = start {
+        fields.push((st, &s[st..]));
+    }
+
+    fields
+}
+
+fn parse_board(fen: &str, offset: usize, placement: &str) -> Result<[[Option<ColoredPiece>; 8]; 8], UciParseError> {
+    let mut board: [[Option<ColoredPiece>; 8]; 8] = [[None; 8]; 8];
+    let ranks: Vec<&str> = placement.split('/').collect();
+
+    if ranks.len() != 8 {
+        return Err(fen_error(fen, offset, placement, "eight ranks"));
+    }
+
+    let mut rank_offset = offset;
+    for (i, rank_str) in ranks.iter().enumerate() {
+        let rank = 7 - i;
+        let mut file = 0;
+
+        for (j, c) in rank_str.char_indices() {
+            let error = || fen_error(fen, rank_offset + j, &c.to_string(), "a piece or a number of empty squares");
+
+            if let Some(d) = c.to_digit(10) {
+                if d == 0 {
+                    return Err(error());
+                }
+                file += d as usize;
+            } else {
+                let piece = piece_from_char(c).ok_or_else(error)?;
+                if file < 8 {
+                    board[rank][file] = Some(piece);
+                }
+                file += 1;
+            }
+
+            if file > 8 {
+                return Err(error());
+            }
+        }
+
+        if file != 8 {
+            return Err(fen_error(fen, rank_offset, rank_str, "a rank of eight squares"));
+        }
+
+        rank_offset += rank_str.len() + 1;
+    }
+
+    Ok(board)
+}
+
+fn parse_castling(fen: &str, offset: usize, castling: &str) -> Result<Vec<CastlingRight>, UciParseError> {
+    if castling == "-" {
+        return Ok(vec![]);
+    }
+
+    let mut rights: Vec<CastlingRight> = vec![];
+    for (i, c) in castling.char_indices() {
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let side = match c.to_ascii_lowercase() {
+            'k' => CastlingSide::KingSide,
+            'q' => CastlingSide::QueenSide,
+            f @ 'a'..='h' => CastlingSide::RookFile(f),
+            _ => return Err(fen_error(fen, offset + i, &c.to_string(), "castling rights")),
+        };
+
+        let right = CastlingRight { color, side };
+        if rights.contains(&right) || rights.len() == 4 {
+            return Err(fen_error(fen, offset + i, &c.to_string(), "castling rights"));
+        }
+        rights.push(right);
+    }
+
+    Ok(rights)
+}
+
+fn fen_error(fen: &str, offset: usize, text: &str, expected: &str) -> UciParseError {
+    let (line, column) = line_col(fen, offset);
+
+    UciParseError::new(UciParseErrorKind::BadFen, line, column, text, vec![expected.to_string()])
+}
+
+fn piece_from_char(c: char) -> Option<ColoredPiece> {
+    let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+    let piece = match c.to_ascii_lowercase() {
+        'p' => Piece::Pawn,
+        'n' => Piece::Knight,
+        'b' => Piece::Bishop,
+        'r' => Piece::Rook,
+        'q' => Piece::Queen,
+        'k' => Piece::King,
+        _ => return None,
+    };
+
+    Some((color, piece))
+}
+
+fn piece_to_char(color: Color, piece: Piece) -> char {
+    let c = match piece {
+        Piece::Pawn => 'p',
+        Piece::Knight => 'n',
+        Piece::Bishop => 'b',
+        Piece::Rook => 'r',
+        Piece::Queen => 'q',
+        Piece::King => 'k',
+    };
+
+    if color == Color::White {
+        c.to_ascii_uppercase()
+    } else {
+        c
+    }
+}
+
+/// Parses a square in the `e3` notation.
+fn parse_square(s: &str) -> Option<Square> {
+    let mut chars = s.chars();
+    let file = chars.next()?.to_ascii_lowercase();
+    let rank = chars.next()?.to_digit(10)?;
+
+    if chars.next().is_some() || !('a'..='h').contains(&file) || !(1..=8).contains(&rank) {
+        return None;
+    }
+
+    Some(make_square(file as usize - 'a' as usize, rank as usize - 1))
+}
+
+#[cfg(not(feature = "chess"))]
+fn make_square(file: usize, rank: usize) -> Square {
+    Square::from((b'a' + file as u8) as char, rank as u8 + 1)
+}
+
+#[cfg(feature = "chess")]
+fn make_square(file: usize, rank: usize) -> Square {
+    Square::make_square(Rank::from_index(rank), File::from_index(file))
+}
+
+/// Returns the file and rank indices (both starting with `0`) of the square.
+#[cfg(not(feature = "chess"))]
+fn square_indices(sq: &Square) -> (usize, usize) {
+    ((sq.file as u8).wrapping_sub(b'a') as usize, (sq.rank as usize).wrapping_sub(1))
+}
+
+#[cfg(feature = "chess")]
+fn square_indices(sq: &Square) -> (usize, usize) {
+    (sq.get_file().to_index(), sq.get_rank().to_index())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn test_parse_startpos() {
+        let pos: FenPosition = START.parse().unwrap();
+
+        assert_eq!(pos.side_to_move, Color::White);
+        assert_eq!(pos.piece_at(4, 0), Some((Color::White, Piece::King)));
+        assert_eq!(pos.piece_at(3, 7), Some((Color::Black, Piece::Queen)));
+        assert_eq!(pos.piece_at(4, 4), None);
+        assert_eq!(pos.castling_rights.len(), 4);
+        assert_eq!(
+            pos.castling_rights[1],
+            CastlingRight {
+                color: Color::White,
+                side: CastlingSide::QueenSide
+            }
+        );
+        assert_eq!(pos.en_passant, None);
+        assert_eq!((pos.halfmove_clock, pos.fullmove_number), (0, 1));
+        assert!(pos.is_valid());
+        assert_eq!(pos.to_string(), START);
+    }
+
+    #[test]
+    fn test_parse_en_passant_and_short_fen() {
+        let pos: FenPosition = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3".parse().unwrap();
+
+        assert_eq!(pos.side_to_move, Color::Black);
+        assert_eq!(pos.en_passant, Some(make_square(4, 2)));
+        assert_eq!((pos.halfmove_clock, pos.fullmove_number), (0, 1));
+        assert!(pos.is_valid());
+        assert_eq!(pos.to_string(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    }
+
+    #[test]
+    fn test_parse_shredder_castling() {
+        let pos: FenPosition = "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w GEge - 0 1".parse().unwrap();
+
+        assert_eq!(
+            pos.castling_rights[0],
+            CastlingRight {
+                color: Color::White,
+                side: CastlingSide::RookFile('g')
+            }
+        );
+        assert!(pos.is_valid());
+        assert_eq!(pos.to_string(), "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w GEge - 0 1");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let e = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1".parse::<FenPosition>().unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::BadFen);
+        assert_eq!(e.column, 1);
+
+        let e = "rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse::<FenPosition>().unwrap_err();
+        assert_eq!((e.column, e.text.as_str()), (14, "x"));
+
+        let e = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1".parse::<FenPosition>().unwrap_err();
+        assert_eq!((e.column, e.text.as_str()), (45, "x"));
+
+        assert!("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse::<FenPosition>().is_err());
+        assert!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKkq - 0 1".parse::<FenPosition>().is_err());
+        assert!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1".parse::<FenPosition>().is_err());
+        assert!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1".parse::<FenPosition>().is_err());
+        assert!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 2".parse::<FenPosition>().is_err());
+        assert!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w".parse::<FenPosition>().is_err());
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(!"rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1".parse::<FenPosition>().unwrap().is_valid());
+        assert!(!"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNP w - - 0 1".parse::<FenPosition>().unwrap().is_valid());
+        assert!(!"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e3 0 1".parse::<FenPosition>().unwrap().is_valid());
+        assert!(!"rnbqkbn1/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse::<FenPosition>().unwrap().is_valid());
+    }
+
+    #[test]
+    fn test_uci_fen_position() {
+        let fen = UciFen::from("8/8/4k3/8/8/4K3/8/8 b - -");
+        let pos = fen.position().unwrap();
+
+        assert_eq!(pos.side_to_move, Color::Black);
+        assert_eq!(UciFen::from(&pos).as_str(), "8/8/4k3/8/8/4K3/8/8 b - - 0 1");
+    }
+}
@@ -6,14 +6,23 @@
 //! grammar is available [here](https://github.com/vampirc/vampirc-uci/blob/master/res/uci.pest).
 //!
 //! See the [README.md](https://github.com/vampirc/vampirc-uci/blob/master/README.md) file for usage instructions.
+//!
+//! Without the default `std` feature, the crate is `no_std` and only requires `alloc`. The message types, the
+//! serializer and the parsers are available in both cases; `UciReader` and the `chess` and `tokio` integrations
+//! require `std`.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
 #[cfg(feature = "chess")]
 extern crate chess;
+#[cfg(feature = "chrono")]
 extern crate chrono;
 extern crate pest;
 #[macro_use]
 extern crate pest_derive;
+#[cfg(feature = "shakmaty")]
+extern crate shakmaty;
 
 #[cfg(feature = "chess")]
 pub use chess::ChessMove;
@@ -21,19 +30,68 @@ pub use chess::ChessMove;
 pub use chess::Piece;
 #[cfg(feature = "chess")]
 pub use chess::Square;
-pub use chrono::Duration;
-pub use pest::error::Error;
 
+pub use self::borrowed::UciInfoAttributeRef;
+pub use self::borrowed::UciMessageRef;
+pub use self::borrowed::UciMovesIter;
+pub use self::borrowed::UciMovesRef;
+pub use self::borrowed::UciOptionConfigRef;
+pub use self::chess960::castling_move;
+pub use self::chess960::convert_castling_moves;
+pub use self::chess960::CastlingMode;
+#[cfg(feature = "tokio")]
+pub use self::codec::UciCodec;
+pub use self::config::DEFAULT_MAX_LINE_LENGTH;
+pub use self::config::ParserConfig;
+pub use self::config::UnknownMessagePolicy;
+pub use self::duration::DurationOutOfRangeError;
+pub use self::duration::UciDuration;
+pub use self::error::UciParseError;
+pub use self::error::UciParseErrorKind;
+#[cfg(feature = "std")]
+pub use self::error::UciReadError;
+pub use self::fen::CastlingRight;
+pub use self::fen::CastlingSide;
+pub use self::fen::FenPosition;
 pub use self::parser::parse;
+pub use self::parser::parse_bytes;
+pub use self::parser::parse_bytes_with_config;
 pub use self::parser::parse_one;
+pub use self::parser::parse_one_ref;
 pub use self::parser::parse_strict;
+pub use self::parser::parse_with_config;
+pub use self::parser::parse_with_config_spanned;
+pub use self::parser::parse_with_errors;
+pub use self::parser::parse_with_castling_mode;
 pub use self::parser::parse_with_unknown;
-pub use self::parser::Rule;
+pub use self::parser::parse_with_unknown_ref;
+pub use self::parser::parse_with_unknown_spanned;
+pub use self::parser::try_parse_one;
+pub use self::parser::try_parse_one_ref;
+pub use self::parser::try_parse_one_with_config;
+#[cfg(all(feature = "shakmaty", feature = "chess"))]
+pub use self::shakmaty_support::{
+    chess_move_from_shakmaty, chess_move_to_shakmaty, chess_piece_from_role, chess_piece_to_role,
+    chess_square_from_shakmaty, chess_square_to_shakmaty,
+};
+#[cfg(feature = "shakmaty")]
+pub use self::shakmaty_support::ShakmatyConversionError;
+pub use self::span::Spanned;
+#[cfg(feature = "std")]
+pub use self::reader::UciReader;
+pub use self::stream::UciStreamParser;
 pub use self::uci::ByteVecUciMessage;
 pub use self::uci::CommunicationDirection;
+#[cfg(feature = "chess")]
+pub use self::uci::is_null_move;
 pub use self::uci::MessageList;
+pub use self::uci::Move;
+#[cfg(feature = "chess")]
+pub use self::uci::null_move;
 pub use self::uci::ProtectionState;
 pub use self::uci::Serializable;
+#[cfg(not(feature = "chess"))]
+pub use self::uci::UciColor;
 pub use self::uci::UciFen;
 pub use self::uci::UciInfoAttribute;
 pub use self::uci::UciMessage;
@@ -49,6 +107,23 @@ pub use self::uci::UciTimeControl;
 
 pub mod uci;
 pub mod parser;
+pub mod chess960;
+pub mod config;
+pub mod duration;
+pub mod error;
+pub mod fen;
+pub mod borrowed;
+mod fast;
+pub mod span;
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod reader;
+#[cfg(feature = "serde")]
+mod serde_support;
+#[cfg(feature = "shakmaty")]
+mod shakmaty_support;
+#[cfg(feature = "tokio")]
+pub mod codec;
 
 #[cfg(test)]
 mod tests {
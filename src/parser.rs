@@ -4,28 +4,46 @@
 //! Behind the scenes, it uses the [PEST parser](https://github.com/pest-parser/pest). The corresponding PEG grammar is
 //! available [here](https://github.com/vampirc/vampirc-uci/blob/master/res/uci.pest).
 
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
 #[cfg(feature = "chess")]
-use std::fmt::Error as FmtError;
-use std::str::FromStr;
+use core::fmt::Error as FmtError;
+use core::ops::Range;
+use core::str::FromStr;
 
-use chrono::Duration;
-use pest::error::Error;
 use pest::iterators::Pair;
-use pest::Parser;
+use pest::{Parser, Span};
 
 #[cfg(feature = "chess")]
 use crate::chess::{ChessMove, Piece, Square};
+#[cfg(feature = "chess")]
+use crate::uci::null_move;
+use crate::borrowed::{UciInfoAttributeRef, UciMessageRef, UciMovesRef, UciOptionConfigRef};
+use crate::chess960::{CastlingMode, CastlingTracker};
+use crate::config::{ParserConfig, UnknownMessagePolicy};
+use crate::duration::UciDuration;
+use crate::error::{line_col, UciParseError, UciParseErrorKind};
+use crate::fast;
+use crate::span::Spanned;
 use crate::uci::ProtectionState;
 use crate::uci::{
-    MessageList, UciFen, UciInfoAttribute, UciMessage, UciSearchControl, UciTimeControl,
+    CommunicationDirection, MessageList, Move, UciMessage, UciSearchControl, UciTimeControl,
 };
 #[cfg(not(feature = "chess"))]
 use crate::uci::{UciMove, UciPiece, UciSquare};
-use crate::UciOptionConfig;
 
-#[derive(Parser)]
-#[grammar = "../res/uci.pest"]
-struct UciParser;
+/// The PEST parser generated from the grammar. It is kept in a private module, so that the `Rule` enum (and with it
+/// the version of PEST) is not part of the public API.
+mod grammar {
+    #[derive(Parser)]
+    #[grammar = "../res/uci.pest"]
+    pub(crate) struct UciParser;
+}
+
+pub(crate) use self::grammar::{Rule, UciParser};
 
 /// Parses the specified `&str s` into a list of `UciMessage`s. Please note that this method will return an `Error` if
 /// any of the input violates the grammar rules.
@@ -45,11 +63,8 @@ struct UciParser;
 /// assert_eq!(messages.len(), 2);
 ///
 /// ```
-pub fn parse_strict(s: &str) -> Result<MessageList, Error<Rule>> {
-    let mut ml = MessageList::new();
-    do_parse_uci(s, Rule::commands, Some(&mut ml))?;
-
-    Ok(ml)
+pub fn parse_strict(s: &str) -> Result<MessageList, UciParseError> {
+    Ok(do_parse_uci(s, Rule::commands)?.into_iter().map(|(_, m)| m.into_owned()).collect())
 }
 
 /// Parses the specified `&str s` into a list of `UciMessage`s. Please note that this method will ignore any
@@ -70,10 +85,12 @@ pub fn parse_strict(s: &str) -> Result<MessageList, Error<Rule>> {
 ///
 /// ```
 pub fn parse(s: &str) -> MessageList {
-    let mut ml = MessageList::new();
-    do_parse_uci(s, Rule::commands_ignore_unknown, Some(&mut ml)).unwrap();
-
-    ml
+    // Messages that fail to convert are skipped, so only a grammar failure (which the permissive top-level rule
+    // does not produce) could result in an error here.
+    match do_parse_uci(s, Rule::commands_ignore_unknown) {
+        Ok(messages) => messages.into_iter().map(|(_, m)| m.into_owned()).collect(),
+        Err(_) => MessageList::new(),
+    }
 }
 
 /// This is like `parse`, except that it returns a `UciMessage::UnknownMessage` variant if it does not recognize the
@@ -88,15 +105,454 @@ pub fn parse(s: &str) -> MessageList {
 /// assert_eq!(messages.len(), 1);
 /// ```
 pub fn parse_with_unknown(s: &str) -> MessageList {
+    parse_with_unknown_spanned(s).into_iter().map(Spanned::into_inner).collect()
+}
+
+/// This is like `parse_with_unknown`, except that each message is returned as a `Spanned<UciMessage>`, which also
+/// carries the byte range, the line number and the original text of the message. This makes it possible to point at
+/// the exact line an unrecognised message came from, or to forward the raw text unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{parse_with_unknown_spanned, UciMessage};
+///
+/// let input = "uci\n  not really a message\nisready\n";
+/// let messages = parse_with_unknown_spanned(input);
+/// assert_eq!(messages.len(), 3);
+///
+/// assert_eq!(messages[1].line, 2);
+/// assert_eq!(messages[1].raw, "  not really a message");
+/// assert_eq!(&input[messages[1].trimmed_span.clone()], "not really a message");
+/// assert_eq!(messages[2].value, UciMessage::IsReady);
+/// ```
+pub fn parse_with_unknown_spanned(s: &str) -> Vec<Spanned<UciMessage>> {
+    match do_parse_uci(s, Rule::commands_with_unknown) {
+        Ok(messages) => {
+            let mut counted = 0;
+            let mut line = 1;
+
+            messages
+                .into_iter()
+                .map(|(range, msg)| {
+                    let mut spanned = Spanned::new(msg.into_owned(), s, line_range(s, range), line);
+                    // Messages come in order, so the newlines only need to be counted from the previous message.
+                    line += s[counted..spanned.span.start].matches('\n').count();
+                    counted = spanned.span.start;
+                    spanned.line = line;
+                    spanned
+                })
+                .collect()
+        }
+        Err(e) => {
+            let range = 0..s.strip_suffix('\n').unwrap_or(s).len();
+            vec![Spanned::new(UciMessage::Unknown(s.trim_end().to_owned(), Some(e)), s, range, 1)]
+        }
+    }
+}
+
+/// Returns the byte range of the line of `s` that contains the message in `range`, without the `\n`.
+fn line_range(s: &str, range: Range<usize>) -> Range<usize> {
+    let text = &s[range.clone()];
+    let message_start = range.start + (text.len() - text.trim_start().len());
+    let start = s[..message_start].rfind('\n').map_or(0, |i| i + 1);
+    let end = s[message_start..].find('\n').map_or(s.len(), |i| message_start + i);
+
+    start..end
+}
+
+/// This is like `parse_with_unknown`, except that the castling moves are rewritten into the notation specified by
+/// `mode`. Use `CastlingMode::Chess960` when the engine is running with the `UCI_Chess960` option and should receive
+/// castling as the king capturing its own rook, or `CastlingMode::Standard` otherwise.
+///
+/// The moves of a `position` message are converted in the context of its own position. The moves of the messages that
+/// follow it (`bestmove`, `go searchmoves` and the `info` attributes `pv`, `currmove`, `refutation` and `currline`)
+/// are converted as played from the last preceding `position` message, see `UciMessage::convert_castling_after`.
+/// Those messages are left unchanged if no `position` message precedes them.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{parse_with_castling_mode, CastlingMode, Serializable};
+///
+/// let messages = parse_with_castling_mode("position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1h1\n",
+///     CastlingMode::Standard);
+/// assert_eq!(messages[0].serialize(), "position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1");
+/// ```
+pub fn parse_with_castling_mode(s: &str, mode: CastlingMode) -> MessageList {
+    let mut castling = CastlingTracker::default();
+
+    parse_with_unknown(s)
+        .into_iter()
+        .map(|m| castling.convert(m, Some(mode)))
+        .collect()
+}
+
+/// Parses the specified `&str s` into a list of `UciMessage`s, line by line, according to the settings in `config`.
+/// See `ParserConfig` for the available settings. An error is only ever returned if `config.unknown_messages` is set
+/// to `UnknownMessagePolicy::Error`.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{parse_with_config, ParserConfig};
+///
+/// let messages = parse_with_config("GO ponder wtime 1000 frobnicate\r\nisready now\n", &ParserConfig::lenient()).unwrap();
+/// assert_eq!(messages.len(), 2);
+///
+/// assert!(parse_with_config("GO ponder wtime 1000\n", &ParserConfig::strict()).is_err());
+/// ```
+pub fn parse_with_config(s: &str, config: &ParserConfig) -> Result<MessageList, UciParseError> {
+    Ok(parse_with_config_spanned(s, config)?.into_iter().map(Spanned::into_inner).collect())
+}
+
+/// This is like `parse_with_config`, except that each message is returned as a `Spanned<UciMessage>`, which also
+/// carries the byte range, the line number and the original text of the message.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{parse_with_config_spanned, ParserConfig};
+///
+/// let messages = parse_with_config_spanned("uci\r\n\nisready\r\n", &ParserConfig::default()).unwrap();
+/// assert_eq!(messages[1].line, 3);
+/// assert_eq!((messages[1].span.clone(), messages[1].raw.as_str()), (6..14, "isready\r"));
+/// assert_eq!(messages[1].trimmed_span, 6..13);
+/// ```
+pub fn parse_with_config_spanned(s: &str, config: &ParserConfig) -> Result<Vec<Spanned<UciMessage>>, UciParseError> {
+    let mut ml = vec![];
+
+    for (line, range, result) in parse_lines(s, config) {
+        if let Some(result) = apply_unknown_policy(&s[range.clone()], result, config) {
+            ml.push(Spanned::new(result?, s, range, line));
+        }
+    }
+
+    Ok(ml)
+}
+
+/// Parses every line of `s` according to the settings in `config`, but without stopping at the first error. Returns
+/// the messages that were parsed successfully, along with an error (with the line and column of the problem) for
+/// every line that could not be parsed. `config.unknown_messages` is not used, as every error is collected. Useful
+/// for validating long engine transcripts in one go; use `ParserConfig::strict()` to check them against the UCI
+/// specification to the letter.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{parse_with_errors, ParserConfig, UciMessage, UciParseErrorKind};
+///
+/// let input = "uci\nbestmove e2e9\nisready\nfrobnicate\nGO infinite\n";
+/// let (messages, errors) = parse_with_errors(input, &ParserConfig::default());
+/// assert_eq!(messages, vec![UciMessage::Uci, UciMessage::IsReady, UciMessage::go_infinite()]);
+///
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!((errors[0].kind, errors[0].line, errors[0].column), (UciParseErrorKind::BadMove, 2, 13));
+/// assert_eq!((errors[1].kind, errors[1].line), (UciParseErrorKind::UnknownCommand, 4));
+///
+/// let (_, errors) = parse_with_errors(input, &ParserConfig::strict());
+/// assert_eq!(errors.len(), 3);
+/// ```
+pub fn parse_with_errors(s: &str, config: &ParserConfig) -> (MessageList, Vec<UciParseError>) {
     let mut ml = MessageList::new();
-    let parse_att = do_parse_uci(s, Rule::commands_with_unknown, Some(&mut ml));
+    let mut errors = vec![];
 
-    if let Err(e) = parse_att {
-        let m = UciMessage::Unknown(s.trim_end().to_owned(), Some(e));
-        return vec![m];
+    for (_, _, result) in parse_lines(s, config) {
+        match result {
+            Ok(msg) => ml.push(msg),
+            Err(e) => errors.push(e),
+        }
     }
 
-    ml
+    (ml, errors)
+}
+
+/// Parses raw bytes into a list of `UciMessage`s, line by line, like `parse_with_unknown`. The input does not need to
+/// be valid UTF-8: invalid sequences (such as a Latin-1 engine name in `id name`) are replaced with `U+FFFD`, so the
+/// rest of the line is still parsed. Lines longer than `DEFAULT_MAX_LINE_LENGTH` bytes are returned as a
+/// `UciMessage::Unknown` holding only the first `DEFAULT_MAX_LINE_LENGTH` bytes and a `LineTooLong` error.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{parse_bytes, UciMessage};
+///
+/// let messages = parse_bytes(b"id name Caf\xe9\r\nuciok\n");
+/// assert_eq!(messages, vec![UciMessage::id_name("Caf\u{fffd}"), UciMessage::UciOk]);
+/// ```
+pub fn parse_bytes(bytes: &[u8]) -> MessageList {
+    // The default configuration keeps unknown messages, so it never returns an error.
+    parse_bytes_with_config(bytes, &ParserConfig::default()).unwrap_or_default()
+}
+
+/// This is like `parse_with_config`, except that it parses raw bytes that do not need to be valid UTF-8, replacing
+/// invalid sequences with `U+FFFD`. The length of each line is checked against `ParserConfig::max_line_length`
+/// before it is decoded, and only the first `max_line_length` bytes of an over-long line are kept in a
+/// `UciMessage::Unknown`.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{parse_bytes_with_config, ParserConfig, UciParseErrorKind, UnknownMessagePolicy};
+///
+/// let config = ParserConfig::default().unknown_messages(UnknownMessagePolicy::Error).max_line_length(1024);
+///
+/// let mut input = b"uci\ninfo string ".to_vec();
+/// input.extend_from_slice(&[b'x'; 2048]);
+///
+/// let e = parse_bytes_with_config(&input, &config).unwrap_err();
+/// assert_eq!((e.kind, e.line), (UciParseErrorKind::LineTooLong, 2));
+/// ```
+pub fn parse_bytes_with_config(bytes: &[u8], config: &ParserConfig) -> Result<MessageList, UciParseError> {
+    let mut ml = MessageList::new();
+    let mut castling = CastlingTracker::default();
+
+    for (line_idx, full_line) in bytes.split_inclusive(|b| *b == b'\n').enumerate() {
+        let line = full_line.strip_suffix(b"\n").unwrap_or(full_line);
+        if let Some(result) = parse_byte_line(line, line_idx + 1, config, &mut castling) {
+            ml.push(result?);
+        }
+    }
+
+    Ok(ml)
+}
+
+/// Splits `s` into lines and parses each non-blank one according to `config`. Yields the line number (starting with
+/// 1), the byte range of the line in `s` (without the `\n`) and the result of parsing it.
+fn parse_lines<'a>(
+    s: &'a str,
+    config: &'a ParserConfig,
+) -> impl Iterator<Item = (usize, Range<usize>, Result<UciMessage, UciParseError>)> + 'a {
+    let mut offset = 0;
+    let mut castling = CastlingTracker::default();
+
+    s.split_inclusive('\n').enumerate().filter_map(move |(line_idx, full_line)| {
+        let start = offset;
+        offset += full_line.len();
+
+        let raw_line = full_line.strip_suffix('\n').unwrap_or(full_line);
+        let line = strip_line_end(raw_line, config);
+        let result = if line.len() > config.max_line_length {
+            Err(line_too_long(line_idx + 1, config.max_line_length))
+        } else {
+            parse_config_line(line, line_idx + 1, config, &mut castling)?
+        };
+
+        Some((line_idx + 1, start..start + raw_line.len(), result))
+    })
+}
+
+/// Strips the `\r` of a `\r\n` line terminator from a line (from which the `\n` has already been removed), if
+/// allowed by `config`.
+fn strip_line_end<'a>(line: &'a str, config: &ParserConfig) -> &'a str {
+    if config.allow_crlf {
+        line.strip_suffix('\r').unwrap_or(line)
+    } else {
+        line
+    }
+}
+
+/// Parses a single line (without the line terminator), which is line number `line_no` of the input, according to
+/// `config`. Returns `None` for blank lines. The unknown message policy is not applied. `castling` keeps track of the
+/// last `position` message for the castling conversion.
+pub(crate) fn parse_config_line(
+    line: &str,
+    line_no: usize,
+    config: &ParserConfig,
+    castling: &mut CastlingTracker,
+) -> Option<Result<UciMessage, UciParseError>> {
+    if line.trim().is_empty() {
+        return None;
+    }
+
+    let result = match parse_line(line, config) {
+        Ok(msg) => Ok(castling.convert(msg.into_owned(), config.castling_mode)),
+        Err(mut e) => {
+            // The error position is relative to the line, not the whole input.
+            e.line = line_no;
+            Err(e)
+        }
+    };
+
+    Some(result)
+}
+
+/// Parses a single line of bytes (without the `\n`), which is line number `line_no` of the input, according to
+/// `config`, including the unknown message policy. Returns `None` if there is no message. The line length is checked
+/// before the line is decoded, and invalid UTF-8 is replaced with `U+FFFD`.
+pub(crate) fn parse_byte_line(
+    line: &[u8],
+    line_no: usize,
+    config: &ParserConfig,
+    castling: &mut CastlingTracker,
+) -> Option<Result<UciMessage, UciParseError>> {
+    let line = match line.strip_suffix(b"\r") {
+        Some(l) if config.allow_crlf => l,
+        _ => line,
+    };
+
+    if line.len() > config.max_line_length {
+        return too_long_byte_line(line, line_no, config);
+    }
+
+    let text = String::from_utf8_lossy(line);
+    let result = parse_config_line(&text, line_no, config, castling)?;
+
+    apply_unknown_policy(&text, result, config)
+}
+
+/// Returns the `LineTooLong` error for line number `line_no`, with the unknown message policy of `config` applied.
+/// Only the first `config.max_line_length` bytes of the line are kept in a `UciMessage::Unknown`.
+pub(crate) fn too_long_byte_line(
+    line: &[u8],
+    line_no: usize,
+    config: &ParserConfig,
+) -> Option<Result<UciMessage, UciParseError>> {
+    let text = String::from_utf8_lossy(&line[..line.len().min(config.max_line_length)]);
+
+    apply_unknown_policy(&text, Err(line_too_long(line_no, config.max_line_length)), config)
+}
+
+/// Applies the unknown message policy of `config` to the result of parsing `line`. Returns `None` if the message is
+/// to be skipped.
+pub(crate) fn apply_unknown_policy(
+    line: &str,
+    result: Result<UciMessage, UciParseError>,
+    config: &ParserConfig,
+) -> Option<Result<UciMessage, UciParseError>> {
+    match result {
+        Ok(msg) => Some(Ok(msg)),
+        Err(e) => match config.unknown_messages {
+            UnknownMessagePolicy::Ignore => None,
+            UnknownMessagePolicy::Keep => Some(Ok(UciMessage::Unknown(line.trim().to_string(), Some(e)))),
+            UnknownMessagePolicy::Error => Some(Err(e)),
+        },
+    }
+}
+
+/// Returns the error for line number `line_no`, which is longer than `max_length` bytes.
+pub(crate) fn line_too_long(line_no: usize, max_length: usize) -> UciParseError {
+    UciParseError::new(
+        UciParseErrorKind::LineTooLong,
+        line_no,
+        max_length + 1,
+        "",
+        vec![format!("at most {} bytes", max_length)],
+    )
+}
+
+/// Parses a single line (without the line terminator) into a message, applying the settings in `config`.
+fn parse_line<'a>(line: &'a str, config: &ParserConfig) -> Result<UciMessageRef<'a>, UciParseError> {
+    if let Some(pos) = line.find('\r') {
+        return Err(line_error(line, pos, "\r", "a line feed"));
+    }
+
+    if config.fast_path {
+        if let Some(m) = fast::parse_line(line, config.case_sensitive) {
+            // A message going the wrong way is left to the PEG parser, which reports it.
+            if config.direction.map_or(true, |d| d == m.direction()) {
+                return Ok(m);
+            }
+        }
+    }
+
+    let rule = match config.direction {
+        Some(CommunicationDirection::GuiToEngine) => Rule::single_engine_bound_message,
+        Some(CommunicationDirection::EngineToGui) => Rule::single_gui_bound_message,
+        None => Rule::single_message_per_line,
+    };
+
+    let mut pairs = match UciParser::parse(rule, line) {
+        Ok(pairs) => pairs,
+        Err(e) => {
+            // Only look at the other direction's messages once the line is known to be invalid, to keep the common
+            // path fast.
+            if rule != Rule::single_message_per_line && UciParser::parse(Rule::single_message_per_line, line).is_ok() {
+                let start = line.len() - line.trim_start().len();
+                let text = line.split_whitespace().next().unwrap_or("");
+                let (line_no, column) = line_col(line, start);
+                let expected = match config.direction {
+                    Some(CommunicationDirection::GuiToEngine) => "an engine-bound message",
+                    _ => "a GUI-bound message",
+                };
+
+                return Err(UciParseError::new(
+                    UciParseErrorKind::WrongDirection,
+                    line_no,
+                    column,
+                    text,
+                    vec![expected.to_string()],
+                ));
+            }
+
+            return Err(UciParseError::from_pest(e, line));
+        }
+    };
+    let pair = match pairs.next() {
+        Some(p) => p,
+        None => return Err(line_error(line, 0, line.trim(), "")),
+    };
+
+    if !config.allow_trailing_garbage {
+        let end = pair.as_span().end();
+        if let Some(start) = line[end..].find(|c: char| !c.is_whitespace()) {
+            let text = line[end + start..].split_whitespace().next().unwrap_or("");
+            return Err(line_error(line, end + start, text, "end of line"));
+        }
+    }
+
+    if config.case_sensitive {
+        check_keyword_case(&pair, line)?;
+    }
+
+    parse_message(pair, config)
+}
+
+/// Returns an error if a keyword in the message is not lowercase. Free text, such as option names, FENs or the
+/// contents of `info string`, is exempt.
+fn check_keyword_case(pair: &Pair<Rule>, line: &str) -> Result<(), UciParseError> {
+    let exempt: Vec<Span> = pair
+        .clone()
+        .into_inner()
+        .flatten()
+        .filter(|p| {
+            matches!(
+                p.as_rule(),
+                Rule::id_text
+                    | Rule::option_name
+                    | Rule::option_value
+                    | Rule::option_name2
+                    | Rule::option_default
+                    | Rule::option_var
+                    | Rule::register_name
+                    | Rule::register_code
+                    | Rule::info_string_string
+                    | Rule::info_any
+                    | Rule::go_unknown
+                    | Rule::fen
+            )
+        })
+        .map(|p| p.as_span())
+        .collect();
+
+    let span = pair.as_span();
+    for (i, c) in span.as_str().char_indices() {
+        let pos = span.start() + i;
+        if c.is_uppercase() && !exempt.iter().any(|e| (e.start()..e.end()).contains(&pos)) {
+            let start = line[..pos].rfind([' ', '\t']).map(|p| p + 1).unwrap_or(0);
+            let text = line[start..].split_whitespace().next().unwrap_or("");
+            return Err(line_error(line, start, text, &text.to_lowercase()));
+        }
+    }
+
+    Ok(())
+}
+
+fn line_error(line: &str, pos: usize, text: &str, expected: &str) -> UciParseError {
+    let (line_no, column) = line_col(line, pos);
+    let expected = if expected.is_empty() { vec![] } else { vec![expected.to_string()] };
+
+    UciParseError::new(UciParseErrorKind::Syntax, line_no, column, text, expected)
 }
 
 /// Parses and returns a single message, with or without a terminating newline. Usually used
@@ -106,6 +562,9 @@ pub fn parse_with_unknown(s: &str) -> MessageList {
 /// Only the first command in the `s` parameter will be returned, if there are more than one in
 /// that string.
 ///
+/// The `info`, `bestmove` and `position` messages are parsed by the fast path whenever possible. Use
+/// `try_parse_one_with_config` to turn it off.
+///
 /// /// # Examples
 ///
 /// ```
@@ -118,852 +577,893 @@ pub fn parse_with_unknown(s: &str) -> MessageList {
 ///     }
 /// ```
 pub fn parse_one(s: &str) -> UciMessage {
-    let r = do_parse_uci(s, Rule::single_message_per_line, None);
+    match try_parse_one(s) {
+        Ok(m) => m,
+        Err(e) => UciMessage::Unknown(s.trim_end().to_owned(), Some(e)),
+    }
+}
 
-    if let Err(e) = r {
-        let m = UciMessage::Unknown(s.trim_end().to_owned(), Some(e));
-        return m;
+/// This is like `parse_one`, except that it returns an `Err(UciParseError)` describing the problem instead of a
+/// `UciMessage::Unknown` variant if the message is unrecognizable.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{try_parse_one, UciMessage, UciParseErrorKind};
+///
+/// assert_eq!(try_parse_one("isready").unwrap(), UciMessage::IsReady);
+///
+/// let err = try_parse_one("bestmove e2e9").unwrap_err();
+/// assert_eq!(err.kind, UciParseErrorKind::BadMove);
+/// assert_eq!((err.line, err.column), (1, 13));
+/// ```
+pub fn try_parse_one(s: &str) -> Result<UciMessage, UciParseError> {
+    try_parse_one_ref(s).map(|m| m.into_owned())
+}
+
+/// This is like `try_parse_one`, except that the message is parsed according to `config`. `parse_one`,
+/// `try_parse_one` and their `_ref` variants always try the fast path first; use this function with
+/// `ParserConfig::fast_path` set to `false` to only use the PEG parser. The unknown message policy does not apply, as
+/// an unrecognizable message is always returned as an error.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{try_parse_one_with_config, ParserConfig, UciMessage, UciParseErrorKind};
+///
+/// let config = ParserConfig::default().fast_path(false);
+/// let msg = try_parse_one_with_config("bestmove e2e4 ponder e7e5", &config).unwrap();
+/// assert!(matches!(msg, UciMessage::BestMove { ponder: Some(_), .. }));
+///
+/// let err = try_parse_one_with_config("ISREADY", &ParserConfig::strict()).unwrap_err();
+/// assert_eq!(err.kind, UciParseErrorKind::Syntax);
+/// ```
+pub fn try_parse_one_with_config(s: &str, config: &ParserConfig) -> Result<UciMessage, UciParseError> {
+    let line = s.split('\n').next().unwrap_or("");
+    let line = strip_line_end(line, config);
+
+    if line.len() > config.max_line_length {
+        return Err(line_too_long(1, config.max_line_length));
+    }
+
+    match parse_config_line(line, 1, config, &mut CastlingTracker::default()) {
+        Some(result) => result,
+        None => Err(UciParseError::new(UciParseErrorKind::UnknownCommand, 1, 1, "", vec![])),
+    }
+}
+
+/// This is like `parse_one`, except that it returns a `UciMessageRef`, which borrows its text (such as the option
+/// names, the FEN or the `info string`) from `s` instead of allocating. Use `UciMessageRef::into_owned()` to obtain a
+/// `UciMessage`.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{parse_one_ref, UciInfoAttributeRef, UciMessageRef};
+///
+/// let line = "info depth 12 string busy pv e2e4 e7e5 g1f3";
+///
+/// if let UciMessageRef::Info(attributes) = parse_one_ref(line) {
+///     assert_eq!(attributes[1], UciInfoAttributeRef::String("busy pv e2e4 e7e5 g1f3"));
+/// } else {
+///     panic!("Expected an info message");
+/// }
+///
+/// if let UciMessageRef::Info(attributes) = parse_one_ref("info depth 12 pv e2e4 e7e5 g1f3") {
+///     match &attributes[1] {
+///         UciInfoAttributeRef::Pv(moves) => {
+///             assert_eq!(moves.as_str(), "e2e4 e7e5 g1f3");
+///             assert_eq!(moves.len(), 3);
+///         }
+///         _ => panic!("Expected a pv attribute"),
+///     }
+/// }
+/// ```
+pub fn parse_one_ref<'a>(s: &'a str) -> UciMessageRef<'a> {
+    match try_parse_one_ref(s) {
+        Ok(m) => m,
+        Err(e) => UciMessageRef::Unknown(s.trim_end(), Some(e)),
+    }
+}
+
+/// This is like `try_parse_one`, except that it returns a `UciMessageRef`, which borrows its text from `s`.
+pub fn try_parse_one_ref<'a>(s: &'a str) -> Result<UciMessageRef<'a>, UciParseError> {
+    if let Some(m) = fast::parse_line(s, false) {
+        return Ok(m);
     }
 
-    if let Some(m) = r.unwrap() {
-        return m;
+    match do_parse_uci(s, Rule::single_message_per_line)?.pop() {
+        Some((_, m)) => Ok(m),
+        None => Err(UciParseError::new(UciParseErrorKind::UnknownCommand, 1, 1, s.trim_end(), vec![])),
     }
+}
+
+/// This is like `parse_with_unknown`, except that it returns a list of `UciMessageRef`s, which borrow their text from
+/// `s`.
+pub fn parse_with_unknown_ref<'a>(s: &'a str) -> Vec<UciMessageRef<'a>> {
+    match do_parse_uci(s, Rule::commands_with_unknown) {
+        Ok(messages) => messages.into_iter().map(|(_, m)| m).collect(),
+        Err(e) => vec![UciMessageRef::Unknown(s.trim_end(), Some(e))],
+    }
+}
+
+/// Parses `s` with the `top_rule`, returning each message along with the byte range of its pair in `s`.
+fn do_parse_uci<'a>(s: &'a str, top_rule: Rule) -> Result<Vec<(Range<usize>, UciMessageRef<'a>)>, UciParseError> {
+    let pairs = UciParser::parse(top_rule, s).map_err(|e| UciParseError::from_pest(e, s))?;
+
+    let mut messages = vec![];
+
+    for pair in pairs {
+        let range = pair.as_span().start()..pair.as_span().end();
+        let msg = match parse_message(pair.clone(), &ParserConfig::default()) {
+            Ok(UciMessageRef::Unknown(text, None)) => {
+                UciMessageRef::Unknown(text, Some(unknown_message_error(&pair, text)))
+            }
+            Ok(m) => m,
+            Err(e) => match top_rule {
+                Rule::commands_ignore_unknown => continue,
+                Rule::commands_with_unknown => UciMessageRef::Unknown(pair.as_str().trim(), Some(e)),
+                _ => return Err(e),
+            },
+        };
 
-    return UciMessage::Unknown(String::new(), None);
+        messages.push((range, msg));
+    }
+
+    Ok(messages)
 }
 
-fn do_parse_uci(
-    s: &str,
-    top_rule: Rule,
-    mut ml: Option<&mut MessageList>,
-) -> Result<Option<UciMessage>, Error<Rule>> {
-    let pairs = UciParser::parse(top_rule, s)?;
-
-    let mut single: Option<UciMessage> = None;
-
-    pairs
-        .map(|pair: Pair<_>| {
-            match pair.as_rule() {
-                Rule::uci => UciMessage::Uci,
-                Rule::debug => {
-                    for sp in pair.into_inner() {
-                        match sp.as_rule() {
-                            Rule::switch => {
-                                return UciMessage::Debug(
-                                    sp.as_span().as_str().eq_ignore_ascii_case("on"),
-                                );
+/// Returns the error that explains why `text`, an unrecognised message matched by `pair`, could not be parsed, the
+/// same way `parse_with_config` would, with its position in the whole input.
+fn unknown_message_error(pair: &Pair<Rule>, text: &str) -> UciParseError {
+    let (line, column) = pair.as_span().start_pos().line_col();
+    let mut e = match do_parse_uci(text, Rule::single_message_per_line) {
+        Err(e) => e,
+        Ok(_) => UciParseError::new(UciParseErrorKind::UnknownCommand, 1, 1, text, vec![]),
+    };
+
+    // `text` is a single line, so only the column needs to be shifted.
+    e.line = line;
+    e.column += column - 1;
+    e
+}
+
+/// Converts a single parsed message pair into a `UciMessageRef`. Values that are grammatically correct but cannot be
+/// represented (such as numbers that are out of range) result in an error.
+fn parse_message<'a>(pair: Pair<'a, Rule>, config: &ParserConfig) -> Result<UciMessageRef<'a>, UciParseError> {
+    let span = pair.as_span();
+
+    let msg = match pair.as_rule() {
+        Rule::uci => UciMessageRef::Uci,
+        Rule::debug => {
+            let on = pair
+                .into_inner()
+                .find(|sp| sp.as_rule() == Rule::switch)
+                .map(|sp| sp.as_span().as_str().eq_ignore_ascii_case("on"))
+                .unwrap_or(false);
+            UciMessageRef::Debug(on)
+        }
+        Rule::isready => UciMessageRef::IsReady,
+        Rule::setoption => {
+            let mut name: &str = "";
+            let mut value: &str = "";
+
+            for sp in pair.into_inner() {
+                if sp.as_rule() == Rule::option_internal {
+                    for spi in sp.into_inner() {
+                        match spi.as_rule() {
+                            Rule::option_name => {
+                                name = spi.as_span().as_str().trim();
                             }
-                            _ => unreachable!(),
-                        }
-                    }
-                    UciMessage::Debug(false)
-                }
-                Rule::isready => UciMessage::IsReady,
-                Rule::setoption => {
-                    let mut name: String = String::default();
-                    let mut value: String = String::default();
-
-                    for sp in pair.into_inner() {
-                        match sp.as_rule() {
-                            Rule::option_internal => {
-                                for spi in sp.into_inner() {
-                                    match spi.as_rule() {
-                                        Rule::option_name => {
-                                            name = spi.as_span().as_str().trim().to_string();
-                                        }
-                                        Rule::option_value => {
-                                            value = spi.as_span().as_str().to_string();
-                                        }
-                                        _ => {}
-                                    }
-                                }
+                            Rule::option_value => {
+                                value = spi.as_span().as_str();
                             }
-                            //                        Rule::value => { value = sp.as_span().as_str().to_string(); },
-                            _ => (),
+                            _ => {}
                         }
                     }
-
-                    let val = if value != String::default() {
-                        Some(value)
-                    } else {
-                        None
-                    };
-                    UciMessage::SetOption { name, value: val }
                 }
-                Rule::register => {
-                    for sp in pair.into_inner() {
-                        match sp.as_rule() {
-                            Rule::register_later => {
-                                return UciMessage::register_later();
-                            }
-                            Rule::register_nc => {
-                                let mut name: &str = "";
+            }
 
-                                for spi in sp.into_inner() {
-                                    match spi.as_rule() {
-                                        Rule::register_name => {
-                                            name = spi.as_span().as_str();
-                                        }
-                                        Rule::register_code => {
-                                            return UciMessage::register_code(name, spi.as_str());
-                                        }
-                                        _ => (),
-                                    }
-                                }
-                            }
-                            _ => unreachable!(),
-                        }
+            let val = if !value.is_empty() { Some(value) } else { None };
+            UciMessageRef::SetOption { name, value: val }
+        }
+        Rule::register => {
+            for sp in pair.into_inner() {
+                match sp.as_rule() {
+                    Rule::register_later => {
+                        return Ok(UciMessageRef::Register {
+                            later: true,
+                            name: None,
+                            code: None,
+                        });
                     }
+                    Rule::register_nc => {
+                        let mut name: &str = "";
 
-                    unreachable!()
-                }
-                Rule::ucinewgame => UciMessage::UciNewGame,
-                Rule::stop => UciMessage::Stop,
-                Rule::ponderhit => UciMessage::PonderHit,
-                Rule::quit => UciMessage::Quit,
-                Rule::position => {
-                    let mut startpos = false;
-                    let mut fen: Option<UciFen> = None;
-                    #[cfg(not(feature = "chess"))] let mut moves: Vec<UciMove> = Default::default();
-                    #[cfg(feature = "chess")] let mut moves: Vec<ChessMove> = Default::default();
-
-                    for sp in pair.into_inner() {
-                        match sp.as_rule() {
-                            Rule::startpos => {
-                                startpos = true;
-                            }
-                            Rule::fen => fen = Some(UciFen::from(sp.as_span().as_str())),
-                            Rule::a_move => {
-                                moves.push(parse_a_move(sp));
+                        for spi in sp.into_inner() {
+                            match spi.as_rule() {
+                                Rule::register_name => {
+                                    name = spi.as_span().as_str();
+                                }
+                                Rule::register_code => {
+                                    return Ok(UciMessageRef::Register {
+                                        later: false,
+                                        name: Some(name),
+                                        code: Some(spi.as_str()),
+                                    });
+                                }
+                                _ => (),
                             }
-                            _ => {}
                         }
                     }
+                    _ => {}
+                }
+            }
 
-                    UciMessage::Position {
-                        startpos,
-                        fen,
-                        moves,
+            return Err(parse_error(span, UciParseErrorKind::Syntax, "register later or a name and code"));
+        }
+        Rule::ucinewgame => UciMessageRef::UciNewGame,
+        Rule::stop => UciMessageRef::Stop,
+        Rule::ponderhit => UciMessageRef::PonderHit,
+        Rule::quit => UciMessageRef::Quit,
+        Rule::position => {
+            let mut startpos = false;
+            let mut fen: Option<&str> = None;
+            let moves = parse_moves_ref(pair.clone());
+
+            for sp in pair.into_inner() {
+                match sp.as_rule() {
+                    Rule::startpos => {
+                        startpos = true;
                     }
+                    Rule::fen => fen = Some(sp.as_span().as_str()),
+                    _ => {}
                 }
-                Rule::go => {
-                    let mut time_control: Option<UciTimeControl> = None;
-                    let mut tl = false;
-                    let mut wtime: Option<i64> = None;
-                    let mut btime: Option<i64> = None;
-                    let mut winc: Option<i64> = None;
-                    let mut binc: Option<i64> = None;
-                    let mut moves_to_go: Option<u8> = None;
-
-                    let mut search: UciSearchControl = UciSearchControl::default();
-
-                    for sp in pair.into_inner() {
-                        match sp.as_rule() {
-                            Rule::go_empty => {}
-                            Rule::go_full => {
-                                for sp_full in sp.into_inner() {
-                                    match sp_full.as_rule() {
-                                        Rule::go_time => {
-                                            for spi in sp_full.into_inner() {
-                                                match spi.as_rule() {
-                                                    Rule::go_ponder => {
-                                                        time_control = Some(UciTimeControl::Ponder);
-                                                    }
-                                                    Rule::go_infinite => {
-                                                        time_control = Some(UciTimeControl::Infinite);
-                                                    }
-                                                    Rule::go_movetime => {
-                                                        time_control = Some(UciTimeControl::MoveTime(
-                                                            Duration::milliseconds(parse_milliseconds(spi)),
-                                                        ));
-                                                    }
-                                                    Rule::go_timeleft => {
-                                                        if !tl {
-                                                            tl = true;
-                                                        }
+            }
 
-                                                        for sspi in spi.into_inner() {
-                                                            match sspi.as_rule() {
-                                                                Rule::wtime => {
-                                                                    wtime = Some(parse_milliseconds(sspi));
-                                                                }
-                                                                Rule::btime => {
-                                                                    btime = Some(parse_milliseconds(sspi));
-                                                                }
-                                                                Rule::winc => {
-                                                                    winc = Some(parse_milliseconds(sspi));
-                                                                }
-                                                                Rule::binc => {
-                                                                    binc = Some(parse_milliseconds(sspi));
-                                                                }
-                                                                Rule::movestogo => {
-                                                                    moves_to_go =
-                                                                        Some(parse_u8(sspi, Rule::digits3));
-                                                                }
-                                                                _ => {}
-                                                            };
-                                                        }
-                                                    }
+            UciMessageRef::Position {
+                startpos,
+                fen,
+                moves,
+            }
+        }
+        Rule::go => {
+            let mut time: UciTimeControl = UciTimeControl::default();
 
-                                                    _ => {}
+            let mut search: UciSearchControl = UciSearchControl::default();
+
+            for sp in pair.into_inner() {
+                match sp.as_rule() {
+                    Rule::go_empty => {}
+                    Rule::go_full => {
+                        for sp_full in sp.into_inner() {
+                            match sp_full.as_rule() {
+                                Rule::go_time => {
+                                    for spi in sp_full.into_inner() {
+                                        match spi.as_rule() {
+                                            Rule::go_ponder => {
+                                                time.ponder = true;
+                                            }
+                                            Rule::go_infinite => {
+                                                time.infinite = true;
+                                            }
+                                            Rule::go_movetime => {
+                                                time.move_time =
+                                                    Some(UciDuration::milliseconds(parse_milliseconds(spi)?));
+                                            }
+                                            Rule::go_timeleft => {
+                                                for sspi in spi.into_inner() {
+                                                    match sspi.as_rule() {
+                                                        Rule::wtime => {
+                                                            time.white_time =
+                                                                Some(UciDuration::milliseconds(parse_milliseconds(sspi)?));
+                                                        }
+                                                        Rule::btime => {
+                                                            time.black_time =
+                                                                Some(UciDuration::milliseconds(parse_milliseconds(sspi)?));
+                                                        }
+                                                        Rule::winc => {
+                                                            time.white_increment =
+                                                                Some(UciDuration::milliseconds(parse_milliseconds(sspi)?));
+                                                        }
+                                                        Rule::binc => {
+                                                            time.black_increment =
+                                                                Some(UciDuration::milliseconds(parse_milliseconds(sspi)?));
+                                                        }
+                                                        Rule::movestogo => {
+                                                            time.moves_to_go =
+                                                                Some(parse_number(sspi, Rule::digits)?);
+                                                        }
+                                                        _ => {}
+                                                    };
                                                 }
                                             }
+
+                                            _ => {}
                                         }
-                                        Rule::go_search => {
-                                            for spi in sp_full.into_inner() {
-                                                match spi.as_rule() {
-                                                    Rule::depth => {
-                                                        search.depth = Some(parse_u8(spi, Rule::digits3));
-                                                    }
-                                                    Rule::mate => {
-                                                        search.mate = Some(parse_u8(spi, Rule::digits3))
-                                                    }
-                                                    Rule::nodes => {
-                                                        search.nodes = Some(parse_u64(spi, Rule::digits12))
-                                                    }
-                                                    Rule::searchmoves => {
-                                                        for mt in spi.into_inner() {
-                                                            search.search_moves.push(parse_a_move(mt));
-                                                        }
-                                                    }
-                                                    _ => {}
+                                    }
+                                }
+                                Rule::go_search => {
+                                    for spi in sp_full.into_inner() {
+                                        match spi.as_rule() {
+                                            Rule::depth => {
+                                                search.depth = Some(parse_number(spi, Rule::digits)?);
+                                            }
+                                            Rule::mate => {
+                                                search.mate = Some(parse_number(spi, Rule::i64)?)
+                                            }
+                                            Rule::nodes => {
+                                                search.nodes = Some(parse_number(spi, Rule::digits)?)
+                                            }
+                                            Rule::searchmoves => {
+                                                for mt in spi.into_inner() {
+                                                    search.search_moves.push(parse_a_move(mt)?);
                                                 }
                                             }
+                                            _ => {}
                                         }
-                                        _ => unreachable!()
                                     }
                                 }
+                                Rule::go_unknown if !config.skip_unknown_go_tokens => {
+                                    return Err(parse_error(
+                                        sp_full.as_span(),
+                                        UciParseErrorKind::Syntax,
+                                        "a go parameter",
+                                    ));
+                                }
+                                _ => {}
                             }
-                            _ => unreachable!(),
                         }
                     }
+                    _ => {}
+                }
+            }
 
-                    if tl {
-                        time_control = Some(UciTimeControl::TimeLeft {
-                            white_time: wtime.map(|millis| Duration::milliseconds(millis)),
-                            black_time: btime.map(|millis| Duration::milliseconds(millis)),
-                            white_increment: winc.map(|millis| Duration::milliseconds(millis)),
-                            black_increment: binc.map(|millis| Duration::milliseconds(millis)),
-                            moves_to_go,
-                        });
-                    }
+            let time_control: Option<UciTimeControl> = if time == UciTimeControl::default() {
+                None
+            } else {
+                Some(time)
+            };
 
-                    let search_control: Option<UciSearchControl>;
-                    if search.is_empty() {
-                        search_control = None
-                    } else {
-                        search_control = Some(search);
-                    }
+            let search_control: Option<UciSearchControl> = if search.is_empty() {
+                None
+            } else {
+                Some(search)
+            };
 
-                    UciMessage::Go {
-                        time_control,
-                        search_control,
+            UciMessageRef::Go {
+                time_control,
+                search_control,
+            }
+        }
+        Rule::id => {
+            for sp in pair.into_inner() {
+                let id_rule: Rule = sp.as_rule();
+                match id_rule {
+                    Rule::id_name | Rule::id_author => {
+                        return parse_id_text(sp, id_rule);
                     }
+                    _ => {}
                 }
-                Rule::id => {
-                    for sp in pair.into_inner() {
-                        let id_rule: Rule = sp.as_rule();
-                        match id_rule {
-                            Rule::id_name | Rule::id_author => {
-                                return parse_id_text(sp, id_rule);
+            }
+
+            return Err(parse_error(span, UciParseErrorKind::Syntax, "a name or an author"));
+        }
+        Rule::uciok => UciMessageRef::UciOk,
+        Rule::readyok => UciMessageRef::ReadyOk,
+        Rule::bestmove => {
+            let mut bm: Option<Move> = None;
+            let mut ponder: Option<Move> = None;
+            for sp in pair.into_inner() {
+                match sp.as_rule() {
+                    Rule::a_move => {
+                        bm = Some(parse_a_move(sp)?);
+                    }
+                    Rule::bestmove_ponder => {
+                        for ssp in sp.into_inner() {
+                            if ssp.as_rule() == Rule::a_move {
+                                ponder = Some(parse_a_move(ssp)?);
                             }
-                            _ => {}
                         }
                     }
+                    _ => {}
+                }
+            }
 
-                    unreachable!()
+            match bm {
+                Some(best_move) => UciMessageRef::BestMove { best_move, ponder },
+                None => return Err(parse_error(span, UciParseErrorKind::BadMove, "a move")),
+            }
+        }
+        Rule::copyprotection | Rule::registration => {
+            let mut ps: Option<ProtectionState> = None;
+            let pc = pair.clone();
+            for sp in pair.into_inner() {
+                match sp.as_rule() {
+                    Rule::protection_checking => ps = Some(ProtectionState::Checking),
+                    Rule::protection_ok => ps = Some(ProtectionState::Ok),
+                    Rule::protection_error => ps = Some(ProtectionState::Error),
+                    _ => {}
                 }
-                Rule::uciok => UciMessage::UciOk,
-                Rule::readyok => UciMessage::ReadyOk,
-                Rule::bestmove => {
-                    #[cfg(not(feature = "chess"))] let mut bm: Option<UciMove> = None;
-                    #[cfg(not(feature = "chess"))] let mut ponder: Option<UciMove> = None;
-                    #[cfg(feature = "chess")] let mut bm: Option<ChessMove> = None;
-                    #[cfg(feature = "chess")] let mut ponder: Option<ChessMove> = None;
-                    for sp in pair.into_inner() {
-                        match sp.as_rule() {
-                            Rule::a_move => {
-                                bm = Some(parse_a_move(sp));
-                            }
-                            Rule::bestmove_ponder => {
-                                for ssp in sp.into_inner() {
-                                    match ssp.as_rule() {
-                                        Rule::a_move => ponder = Some(parse_a_move(ssp)),
-                                        _ => {}
-                                    }
+            }
+
+            let ps = match ps {
+                Some(state) => state,
+                None => return Err(parse_error(span, UciParseErrorKind::Syntax, "a protection state")),
+            };
+
+            if pc.as_rule() == Rule::copyprotection {
+                UciMessageRef::CopyProtection(ps)
+            } else {
+                UciMessageRef::Registration(ps)
+            }
+        }
+        Rule::option => {
+            let mut name: Option<&str> = None;
+            let mut opt_default: Option<&str> = None;
+            let mut opt_min: Option<i64> = None;
+            let mut opt_max: Option<i64> = None;
+            let mut opt_var: Vec<&str> = Vec::default();
+            let mut type_pair: Option<Pair<Rule>> = None;
+
+            for sp in pair.into_inner() {
+                match sp.as_rule() {
+                    Rule::option_name2 => {
+                        name = Some(sp.as_span().as_str());
+                    }
+                    Rule::option_type => {
+                        for spi in sp.into_inner() {
+                            match spi.as_rule() {
+                                Rule::option_check
+                                | Rule::option_spin
+                                | Rule::option_combo
+                                | Rule::option_string
+                                | Rule::option_button => {
+                                    type_pair = Some(spi);
                                 }
+                                _ => {}
                             }
-                            _ => {}
                         }
                     }
-
-                    UciMessage::BestMove {
-                        best_move: bm.unwrap(),
-                        ponder,
+                    Rule::option_default => {
+                        opt_default = Some(sp.as_span().as_str());
                     }
-                }
-                Rule::copyprotection | Rule::registration => {
-                    let mut ps: Option<ProtectionState> = None;
-                    let pc = pair.clone();
-                    for sp in pair.into_inner() {
-                        match sp.as_rule() {
-                            Rule::protection_checking => ps = Some(ProtectionState::Checking),
-                            Rule::protection_ok => ps = Some(ProtectionState::Ok),
-                            Rule::protection_error => ps = Some(ProtectionState::Error),
-                            _ => {}
-                        }
+                    Rule::option_min => {
+                        opt_min = Some(parse_number(sp, Rule::i64)?);
                     }
-
-                    if pc.as_rule() == Rule::copyprotection {
-                        UciMessage::CopyProtection(ps.unwrap())
-                    } else {
-                        UciMessage::Registration(ps.unwrap())
+                    Rule::option_max => {
+                        opt_max = Some(parse_number(sp, Rule::i64)?);
+                    }
+                    Rule::option_var => {
+                        opt_var.push(sp.as_span().as_str());
                     }
+                    _ => {}
                 }
-                Rule::option => {
-                    let mut name: Option<&str> = None;
-                    let mut opt_default: Option<&str> = None;
-                    let mut opt_min: Option<i64> = None;
-                    let mut opt_max: Option<i64> = None;
-                    let mut opt_var: Vec<String> = Vec::default();
-                    let mut type_pair: Option<Pair<Rule>> = None;
-
-                    for sp in pair.into_inner() {
-                        match sp.as_rule() {
-                            Rule::option_name2 => {
-                                name = Some(sp.as_span().as_str());
+            }
+
+            let name = name.unwrap_or_default();
+            let uoc: UciOptionConfigRef = match type_pair.map(|tp| tp.as_rule()) {
+                Some(Rule::option_check) => UciOptionConfigRef::Check {
+                    name,
+                    default: if let Some(def) = opt_default {
+                        match def.to_lowercase().as_str() {
+                            "true" => Some(true),
+                            "false" => Some(false),
+                            _ => None,
+                        }
+                    } else {
+                        None
+                    },
+                },
+                Some(Rule::option_spin) => UciOptionConfigRef::Spin {
+                    name,
+                    default: opt_default.and_then(|def| str::parse::<i64>(def).ok()),
+                    min: opt_min,
+                    max: opt_max,
+                },
+                Some(Rule::option_combo) => UciOptionConfigRef::Combo {
+                    name,
+                    default: if let Some(def) = opt_default {
+                        if def.eq_ignore_ascii_case("<empty>") {
+                            Some("")
+                        } else {
+                            Some(def)
+                        }
+                    } else {
+                        None
+                    },
+                    var: opt_var,
+                },
+                Some(Rule::option_string) => UciOptionConfigRef::String {
+                    name,
+                    default: if let Some(def) = opt_default {
+                        if def.eq_ignore_ascii_case("<empty>") {
+                            Some("")
+                        } else {
+                            Some(def)
+                        }
+                    } else {
+                        None
+                    },
+                },
+                Some(Rule::option_button) => UciOptionConfigRef::Button {
+                    name,
+                },
+                _ => return Err(parse_error(span, UciParseErrorKind::Syntax, "an option type")),
+            };
+
+            UciMessageRef::Option(uoc)
+        }
+        Rule::info => {
+            let mut info_attr: Vec<UciInfoAttributeRef> = vec![];
+
+            for sp in pair.into_inner() {
+                if sp.as_rule() == Rule::info_attribute {
+                    for spi in sp.into_inner() {
+                        match spi.as_rule() {
+                            Rule::info_depth => {
+                                let info_depth = UciInfoAttributeRef::Depth(parse_number(spi, Rule::digits)?);
+                                info_attr.push(info_depth);
+                                break;
+                            }
+                            Rule::info_seldepth => {
+                                let info_depth = UciInfoAttributeRef::SelDepth(parse_number(spi, Rule::digits)?);
+                                info_attr.push(info_depth);
+                                break;
+                            }
+                            Rule::info_time => {
+                                let info_time = UciInfoAttributeRef::Time(UciDuration::milliseconds(parse_number(spi, Rule::digits)?));
+                                info_attr.push(info_time);
+                                break;
+                            }
+                            Rule::info_nodes => {
+                                let info_nodes = UciInfoAttributeRef::Nodes(parse_number(spi, Rule::digits)?);
+                                info_attr.push(info_nodes);
+                                break;
+                            }
+                            Rule::info_currmovenum => {
+                                let an_info = UciInfoAttributeRef::CurrMoveNum(parse_number(spi, Rule::digits)?);
+                                info_attr.push(an_info);
+                                break;
+                            }
+                            Rule::info_hashfull => {
+                                let an_info = UciInfoAttributeRef::HashFull(parse_number(spi, Rule::digits)?);
+                                info_attr.push(an_info);
+                                break;
                             }
-                            Rule::option_type => {
-                                for spi in sp.into_inner() {
-                                    match spi.as_rule() {
-                                        Rule::option_check
-                                        | Rule::option_spin
-                                        | Rule::option_combo
-                                        | Rule::option_string
-                                        | Rule::option_button => {
-                                            type_pair = Some(spi);
-                                        }
-                                        _ => {}
-                                    }
-                                }
+                            Rule::info_nps => {
+                                let an_info = UciInfoAttributeRef::Nps(parse_number(spi, Rule::digits)?);
+                                info_attr.push(an_info);
+                                break;
                             }
-                            Rule::option_default => {
-                                opt_default = Some(sp.as_span().as_str());
+                            Rule::info_tbhits => {
+                                let an_info = UciInfoAttributeRef::TbHits(parse_number(spi, Rule::digits)?);
+                                info_attr.push(an_info);
+                                break;
                             }
-                            Rule::option_min => {
-                                opt_min = Some(parse_i64(sp, Rule::i64));
+                            Rule::info_sbhits => {
+                                let an_info = UciInfoAttributeRef::SbHits(parse_number(spi, Rule::digits)?);
+                                info_attr.push(an_info);
+                                break;
                             }
-                            Rule::option_max => {
-                                opt_max = Some(parse_i64(sp, Rule::i64));
+                            Rule::info_cpuload => {
+                                let an_info = UciInfoAttributeRef::CpuLoad(parse_number(spi, Rule::digits)?);
+                                info_attr.push(an_info);
+                                break;
                             }
-                            Rule::option_var => {
-                                opt_var.push(String::from(sp.as_span().as_str()));
+                            Rule::info_multipv => {
+                                let an_info = UciInfoAttributeRef::MultiPv(parse_number(spi, Rule::digits)?);
+                                info_attr.push(an_info);
+                                break;
                             }
-                            _ => unreachable!(),
-                        }
-                    }
-
-                    let uoc: UciOptionConfig = match type_pair.unwrap().as_rule() {
-                        Rule::option_check => UciOptionConfig::Check {
-                            name: String::from(name.unwrap()),
-                            default: if let Some(def) = opt_default {
-                                match def.to_lowercase().as_str() {
-                                    "true" => Some(true),
-                                    "false" => Some(false),
-                                    _ => None,
-                                }
-                            } else {
-                                None
-                            },
-                        },
-                        Rule::option_spin => UciOptionConfig::Spin {
-                            name: String::from(name.unwrap()),
-                            default: if let Some(def) = opt_default {
-                                if let Ok(def_i64) = str::parse::<i64>(def) {
-                                    Some(def_i64)
-                                } else {
-                                    None
+                            Rule::info_pv => {
+                                info_attr.push(UciInfoAttributeRef::Pv(parse_moves_ref(spi)));
+                                break;
+                            }
+                            Rule::info_refutation => {
+                                info_attr.push(UciInfoAttributeRef::Refutation(parse_moves_ref(spi)));
+                                break;
+                            }
+                            Rule::info_currline => {
+                                let line = parse_moves_ref(spi.clone());
+                                let mut cpu_nr: Option<u16> = None;
+                                for spii in spi.into_inner() {
+                                    if spii.as_rule() == Rule::info_cpunr {
+                                        cpu_nr = Some(parse_number(spii, Rule::digits)?);
+                                    }
                                 }
-                            } else {
-                                None
-                            },
-                            min: if let Some(min1) = opt_min {
-                                Some(min1)
-                            } else {
-                                None
-                            },
-                            max: if let Some(max1) = opt_max {
-                                Some(max1)
-                            } else {
-                                None
-                            },
-                        },
-                        Rule::option_combo => UciOptionConfig::Combo {
-                            name: String::from(name.unwrap()),
-                            default: if let Some(def) = opt_default {
-                                if def.eq_ignore_ascii_case("<empty>") {
-                                    Some(String::from(""))
-                                } else {
-                                    Some(String::from(def))
+                                info_attr.push(UciInfoAttributeRef::CurrLine { cpu_nr, line });
+                                break;
+                            }
+                            Rule::info_string => {
+                                // A bare `info string` is an empty string, not an unknown attribute.
+                                let s = spi
+                                    .into_inner()
+                                    .find(|spii| spii.as_rule() == Rule::info_string_string)
+                                    .map_or("", |spii| spii.as_span().as_str());
+                                info_attr.push(UciInfoAttributeRef::String(s));
+                                break;
+                            }
+                            Rule::info_currmove => {
+                                for spii in spi.into_inner() {
+                                    if spii.as_rule() == Rule::a_move {
+                                        let an_info = UciInfoAttributeRef::CurrMove(
+                                            parse_a_move(spii)?,
+                                        );
+                                        info_attr.push(an_info);
+                                        break;
+                                    }
                                 }
-                            } else {
-                                None
-                            },
-                            var: opt_var,
-                        },
-                        Rule::option_string => UciOptionConfig::String {
-                            name: String::from(name.unwrap()),
-                            default: if let Some(def) = opt_default {
-                                if def.eq_ignore_ascii_case("<empty>") {
-                                    Some(String::from(""))
-                                } else {
-                                    Some(String::from(def))
+                                break;
+                            }
+                            Rule::info_score => {
+                                let mut cp: Option<i32> = None;
+                                let mut mate: Option<i16> = None;
+                                let mut lb: Option<bool> = None;
+                                let mut ub: Option<bool> = None;
+
+                                for spii in spi.into_inner() {
+                                    match spii.as_rule() {
+                                        Rule::info_cp => cp = Some(parse_number(spii, Rule::i64)?),
+                                        Rule::info_mate => mate = Some(parse_number(spii, Rule::i64)?),
+                                        Rule::info_lowerbound => lb = Some(true),
+                                        Rule::info_upperbound => ub = Some(true),
+                                        _ => {}
+                                    }
                                 }
-                            } else {
-                                None
-                            },
-                        },
-                        Rule::option_button => UciOptionConfig::Button {
-                            name: String::from(name.unwrap()),
-                        },
-                        _ => unreachable!(),
-                    };
-
-                    UciMessage::Option(uoc)
-                }
-                Rule::info => {
-                    let mut info_attr: Vec<UciInfoAttribute> = vec![];
-
-                    for sp in pair.into_inner() {
-                        match sp.as_rule() {
-                            Rule::info_attribute => {
-                                for spi in sp.into_inner() {
-                                    match spi.as_rule() {
-                                        Rule::info_depth => {
-                                            let info_depth = UciInfoAttribute::Depth(parse_u8(
-                                                spi,
-                                                Rule::digits3,
-                                            ));
-                                            info_attr.push(info_depth);
-                                            break;
-                                        }
-                                        Rule::info_seldepth => {
-                                            let info_depth = UciInfoAttribute::SelDepth(parse_u8(
-                                                spi,
-                                                Rule::digits3,
-                                            ));
-                                            info_attr.push(info_depth);
-                                            break;
-                                        }
-                                        Rule::info_time => {
-                                            let info_time = UciInfoAttribute::Time(Duration::milliseconds(parse_i64(
-                                                spi,
-                                                Rule::digits12,
-                                            )));
-                                            info_attr.push(info_time);
-                                            break;
-                                        }
-                                        Rule::info_nodes => {
-                                            let info_nodes = UciInfoAttribute::Nodes(parse_u64(
-                                                spi,
-                                                Rule::digits12,
-                                            ));
-                                            info_attr.push(info_nodes);
-                                            break;
-                                        }
-                                        Rule::info_currmovenum => {
-                                            let an_info = UciInfoAttribute::CurrMoveNum(parse_u64(
-                                                spi,
-                                                Rule::digits12,
-                                            )
-                                                as u16);
-                                            info_attr.push(an_info);
-                                            break;
-                                        }
-                                        Rule::info_hashfull => {
-                                            let an_info = UciInfoAttribute::HashFull(parse_u64(
-                                                spi,
-                                                Rule::digits12,
-                                            )
-                                                as u16);
-                                            info_attr.push(an_info);
-                                            break;
-                                        }
-                                        Rule::info_nps => {
-                                            let an_info = UciInfoAttribute::Nps(parse_u64(
-                                                spi,
-                                                Rule::digits12,
-                                            ));
-                                            info_attr.push(an_info);
-                                            break;
-                                        }
-                                        Rule::info_tbhits => {
-                                            let an_info = UciInfoAttribute::TbHits(parse_u64(
-                                                spi,
-                                                Rule::digits12,
-                                            ));
-                                            info_attr.push(an_info);
-                                            break;
-                                        }
-                                        Rule::info_sbhits => {
-                                            let an_info = UciInfoAttribute::SbHits(parse_u64(
-                                                spi,
-                                                Rule::digits12,
-                                            ));
-                                            info_attr.push(an_info);
-                                            break;
-                                        }
-                                        Rule::info_cpuload => {
-                                            let an_info = UciInfoAttribute::CpuLoad(parse_u64(
-                                                spi,
-                                                Rule::digits12,
-                                            )
-                                                as u16);
-                                            info_attr.push(an_info);
-                                            break;
-                                        }
-                                        Rule::info_multipv => {
-                                            let an_info = UciInfoAttribute::MultiPv(parse_u64(
-                                                spi,
-                                                Rule::digits12,
-                                            )
-                                                as u16);
-                                            info_attr.push(an_info);
-                                            break;
-                                        }
-                                        Rule::info_pv => {
-                                            #[cfg(not(feature = "chess"))] let mut mv: Vec<UciMove> = vec![];
-                                            #[cfg(feature = "chess")] let mut mv: Vec<ChessMove> = vec![];
-                                            for spii in spi.into_inner() {
-                                                match spii.as_rule() {
-                                                    Rule::a_move => {
-                                                        let a_move = parse_a_move(spii);
-                                                        mv.push(a_move);
-                                                    }
-                                                    _ => {}
-                                                }
-                                            }
-                                            info_attr.push(UciInfoAttribute::Pv(mv));
-                                            break;
-                                        }
-                                        Rule::info_refutation => {
-                                            #[cfg(not(feature = "chess"))] let mut mv: Vec<UciMove> = vec![];
-                                            #[cfg(feature = "chess")] let mut mv: Vec<ChessMove> = vec![];
-                                            for spii in spi.into_inner() {
-                                                match spii.as_rule() {
-                                                    Rule::a_move => {
-                                                        let a_move = parse_a_move(spii);
-                                                        mv.push(a_move);
-                                                    }
-                                                    _ => {}
-                                                }
-                                            }
-                                            info_attr.push(UciInfoAttribute::Refutation(mv));
-                                            break;
-                                        }
-                                        Rule::info_currline => {
-                                            #[cfg(not(feature = "chess"))] let mut mv: Vec<UciMove> = vec![];
-                                            #[cfg(feature = "chess")] let mut mv: Vec<ChessMove> = vec![];
-                                            let mut cpu_nr: Option<u16> = None;
-                                            for spii in spi.into_inner() {
-                                                match spii.as_rule() {
-                                                    Rule::a_move => {
-                                                        let a_move = parse_a_move(spii);
-                                                        mv.push(a_move);
-                                                    }
-                                                    Rule::info_cpunr => {
-                                                        cpu_nr =
-                                                            Some(parse_u64(spii, Rule::digits3)
-                                                                as u16);
-                                                    }
-                                                    _ => {}
-                                                }
-                                            }
-                                            info_attr.push(UciInfoAttribute::CurrLine {
-                                                cpu_nr,
-                                                line: mv,
-                                            });
-                                            break;
-                                        }
-                                        Rule::info_string => {
-                                            for spii in spi.into_inner() {
-                                                match spii.as_rule() {
-                                                    Rule::info_string_string => {
-                                                        let an_info = UciInfoAttribute::String(
-                                                            spii.as_span().as_str().to_owned(),
-                                                        );
-                                                        info_attr.push(an_info);
-                                                        break;
-                                                    }
-                                                    _ => {}
-                                                }
-                                            }
-                                            break;
-                                        }
-                                        Rule::info_currmove => {
-                                            for spii in spi.into_inner() {
-                                                match spii.as_rule() {
-                                                    Rule::a_move => {
-                                                        let an_info = UciInfoAttribute::CurrMove(
-                                                            parse_a_move(spii),
-                                                        );
-                                                        info_attr.push(an_info);
-                                                        break;
-                                                    }
-                                                    _ => {}
-                                                }
-                                            }
-                                            break;
-                                        }
-                                        Rule::info_score => {
-                                            let mut cp: Option<i32> = None;
-                                            let mut mate: Option<i8> = None;
-                                            let mut lb: Option<bool> = None;
-                                            let mut ub: Option<bool> = None;
-
-                                            for spii in spi.into_inner() {
-                                                match spii.as_rule() {
-                                                    Rule::info_cp => cp = Some(parse_i64(spii, Rule::i64) as i32),
-                                                    Rule::info_mate => mate = Some(parse_i64(spii, Rule::i64) as i8),
-                                                    Rule::info_lowerbound => lb = Some(true),
-                                                    Rule::info_upperbound => ub = Some(true),
-                                                    _ => {}
-                                                }
-                                            }
 
-                                            info_attr.push(UciInfoAttribute::Score {
-                                                cp,
-                                                mate,
-                                                lower_bound: lb,
-                                                upper_bound: ub,
-                                            });
-                                        }
-                                        Rule::info_any => {
-                                            let mut s: Option<String> = None;
-                                            let mut t: Option<String> = None;
-
-                                            for spii in spi.into_inner() {
-                                                match spii.as_rule() {
-                                                    Rule::token => {
-                                                        t = Some(
-                                                            spii.as_span().as_str().to_owned(),
-                                                        );
-                                                    }
-                                                    Rule::info_string_string => {
-                                                        s = Some(
-                                                            spii.as_span().as_str().to_owned(),
-                                                        );
-                                                    }
-                                                    _ => {}
-                                                }
-                                            }
-                                            let an_info =
-                                                UciInfoAttribute::Any(t.unwrap(), s.unwrap());
-                                            info_attr.push(an_info);
-                                            break;
-                                        }
-                                        _ => unreachable!(),
+                                info_attr.push(UciInfoAttributeRef::Score {
+                                    cp,
+                                    mate,
+                                    lower_bound: lb,
+                                    upper_bound: ub,
+                                });
+                                break;
+                            }
+                            Rule::info_wdl => {
+                                let wdl_span = spi.as_span();
+                                let wdl = spi
+                                    .into_inner()
+                                    .filter(|spii| spii.as_rule() == Rule::digits)
+                                    .map(|spii| parse_number_str(spii.as_span()))
+                                    .collect::<Result<Vec<u16>, UciParseError>>()?;
+
+                                match wdl[..] {
+                                    [win, draw, loss] => info_attr.push(UciInfoAttributeRef::Wdl { win, draw, loss }),
+                                    _ => return Err(parse_error(wdl_span, UciParseErrorKind::Syntax, "three wdl values")),
+                                }
+                                break;
+                            }
+                            Rule::info_any => {
+                                let mut s: Option<&str> = None;
+                                let mut t: Option<&str> = None;
+
+                                for spii in spi.into_inner() {
+                                    match spii.as_rule() {
+                                        Rule::token => t = Some(spii.as_span().as_str()),
+                                        Rule::info_any_value => s = Some(spii.as_span().as_str()),
+                                        _ => {}
                                     }
                                 }
+                                if config.keep_unknown_info_attributes {
+                                    let an_info =
+                                        UciInfoAttributeRef::Any(t.unwrap_or_default(), s.unwrap_or_default());
+                                    info_attr.push(an_info);
+                                }
+                                break;
                             }
-                            _ => unreachable!(),
+                            _ => {}
                         }
                     }
-
-                    UciMessage::Info(info_attr)
-                }
-                Rule::something_produced => {
-                    UciMessage::Unknown(pair.as_span().as_str().to_string(), None)
                 }
-                Rule::something_produced_nl => {
-                    UciMessage::Unknown(pair.as_span().as_str().trim_end().to_string(), None)
-                }
-
-                _ => unreachable!(),
             }
-        })
-        .for_each(|msg| {
-            if let Some(a_ml) = &mut ml {
-                (*a_ml).push(msg);
-            } else {
-                single = Some(msg);
-            }
-        });
 
-    Ok(single)
-}
-
-fn parse_id_text(id_pair: Pair<Rule>, rule: Rule) -> UciMessage {
-    for sp in id_pair.into_inner() {
-        match sp.as_rule() {
-            Rule::id_text => {
-                let text = sp.as_span().as_str();
-                match rule {
-                    Rule::id_name => {
-                        return UciMessage::Id {
-                            name: Some(String::from(text)),
-                            author: None,
-                        };
-                    }
-                    Rule::id_author => {
-                        return UciMessage::Id {
-                            author: Some(String::from(text)),
-                            name: None,
-                        };
-                    }
-                    _ => unreachable!(),
-                }
-            }
-            _ => {}
+            UciMessageRef::Info(info_attr)
         }
-    }
+        Rule::something_produced => {
+            UciMessageRef::Unknown(pair.as_span().as_str(), None)
+        }
+        Rule::something_produced_nl => {
+            UciMessageRef::Unknown(pair.as_span().as_str().trim_end(), None)
+        }
+
+        _ => return Err(parse_error(span, UciParseErrorKind::UnknownCommand, "")),
+    };
 
-    unreachable!();
+    Ok(msg)
 }
 
-#[cfg(not(feature = "chess"))]
-fn parse_square(sq_pair: Pair<Rule>) -> UciSquare {
-    let mut file: char = '\0';
-    let mut rank: u8 = 0;
+fn parse_id_text<'a>(id_pair: Pair<'a, Rule>, rule: Rule) -> Result<UciMessageRef<'a>, UciParseError> {
+    let span = id_pair.as_span();
 
-    match sq_pair.as_rule() {
-        Rule::square => {
-            for sp in sq_pair.into_inner() {
-                match sp.as_rule() {
-                    Rule::file => {
-                        file = sp.as_span().as_str().chars().into_iter().next().unwrap();
-                    }
-                    Rule::rank => {
-                        rank = str::parse(sp.as_span().as_str()).unwrap();
-                    }
-                    _ => unreachable!(),
+    for sp in id_pair.into_inner() {
+        if sp.as_rule() == Rule::id_text {
+            let text = sp.as_span().as_str();
+            match rule {
+                Rule::id_name => {
+                    return Ok(UciMessageRef::Id {
+                        name: Some(text),
+                        author: None,
+                    });
+                }
+                Rule::id_author => {
+                    return Ok(UciMessageRef::Id {
+                        author: Some(text),
+                        name: None,
+                    });
                 }
+                _ => {}
             }
         }
-        _ => unreachable!(),
     }
 
-    UciSquare::from(file, rank)
+    Err(parse_error(span, UciParseErrorKind::Syntax, "a name or an author"))
 }
 
-#[cfg(feature = "chess")]
-fn parse_square(sq_pair: Pair<Rule>) -> Square {
-    let mut file: char = '\0';
-    let mut rank: u8 = 0;
+/// Returns the moves (the `a_move` pairs) inside `pair` as a `UciMovesRef`, borrowing the text from the input. The
+/// grammar has already validated every move.
+fn parse_moves_ref<'a>(pair: Pair<'a, Rule>) -> UciMovesRef<'a> {
+    let text = pair.as_str();
+    let base = pair.as_span().start();
+    let mut range: Option<(usize, usize)> = None;
 
-    match sq_pair.as_rule() {
-        Rule::square => {
-            for sp in sq_pair.into_inner() {
-                match sp.as_rule() {
-                    Rule::file => {
-                        file = sp.as_span().as_str().chars().into_iter().next().unwrap();
-                    }
-                    Rule::rank => {
-                        rank = str::parse(sp.as_span().as_str()).unwrap();
-                    }
-                    _ => unreachable!(),
-                }
-            }
+    for sp in pair.into_inner() {
+        if sp.as_rule() == Rule::a_move {
+            let span = sp.as_span();
+            range = Some((range.map_or(span.start(), |r| r.0), span.end()));
         }
-        _ => unreachable!(),
     }
 
-    Square::from_str(format!("{}{}", file.to_string(), rank.to_string()).as_str()).unwrap()
+    match range {
+        Some((start, end)) => UciMovesRef::new(&text[start - base..end - base]),
+        None => UciMovesRef::default(),
+    }
 }
 
-fn parse_milliseconds(pair: Pair<Rule>) -> i64 {
-    for sp in pair.into_inner() {
+/// Returns the lowercase file and the rank of a `square` pair.
+fn parse_file_rank(sq_pair: Pair<Rule>) -> Result<(char, u8), UciParseError> {
+    let span = sq_pair.as_span();
+    let mut file: Option<char> = None;
+    let mut rank: Option<u8> = None;
+
+    for sp in sq_pair.into_inner() {
         match sp.as_rule() {
-            Rule::milliseconds => {
-                return str::parse::<i64>(sp.as_span().as_str()).unwrap();
+            Rule::file => {
+                file = sp.as_span().as_str().chars().next().map(|c| c.to_ascii_lowercase());
+            }
+            Rule::rank => {
+                rank = Some(parse_number_str(sp.as_span())?);
             }
             _ => {}
         }
     }
 
-    0
+    match (file, rank) {
+        (Some(file), Some(rank)) => Ok((file, rank)),
+        _ => Err(parse_error(span, UciParseErrorKind::BadMove, "a square")),
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+fn parse_square(sq_pair: Pair<Rule>) -> Result<UciSquare, UciParseError> {
+    let (file, rank) = parse_file_rank(sq_pair)?;
+
+    Ok(UciSquare::from(file, rank))
 }
 
-fn parse_u8(pair: Pair<Rule>, rule: Rule) -> u8 {
-    for sp in pair.into_inner() {
-        if sp.as_rule() == rule {
-            return str::parse::<u8>(sp.as_span().as_str()).unwrap();
-        }
-    }
+#[cfg(feature = "chess")]
+fn parse_square(sq_pair: Pair<Rule>) -> Result<Square, UciParseError> {
+    let span = sq_pair.as_span();
+    let (file, rank) = parse_file_rank(sq_pair)?;
+
+    Square::from_str(format!("{}{}", file, rank).as_str())
+        .map_err(|_| parse_error(span, UciParseErrorKind::BadMove, "a square"))
+}
 
-    0
+fn parse_milliseconds(pair: Pair<Rule>) -> Result<i64, UciParseError> {
+    parse_number(pair, Rule::milliseconds)
 }
 
-fn parse_u64(pair: Pair<Rule>, rule: Rule) -> u64 {
+/// Parses the first inner pair of `pair` that matches `rule` as a number. An error is returned if the value does not
+/// fit into `T`.
+fn parse_number<T: FromStr>(pair: Pair<Rule>, rule: Rule) -> Result<T, UciParseError> {
+    let span = pair.as_span();
+
     for sp in pair.into_inner() {
         if sp.as_rule() == rule {
-            return str::parse::<u64>(sp.as_span().as_str()).unwrap();
+            return parse_number_str(sp.as_span());
         }
     }
 
-    0
+    Err(parse_error(span, UciParseErrorKind::Syntax, "a number"))
 }
 
-fn parse_i64(pair: Pair<Rule>, rule: Rule) -> i64 {
-    for sp in pair.into_inner() {
-        if sp.as_rule() == rule {
-            return str::parse::<i64>(sp.as_span().as_str()).unwrap();
-        }
-    }
+fn parse_number_str<T: FromStr>(span: Span) -> Result<T, UciParseError> {
+    str::parse::<T>(span.as_str())
+        .map_err(|_| parse_error(span, UciParseErrorKind::OutOfRange, ""))
+}
+
+fn parse_error(span: Span, kind: UciParseErrorKind, expected: &str) -> UciParseError {
+    let (line, column) = span.start_pos().line_col();
+    let expected = if expected.is_empty() { vec![] } else { vec![expected.to_string()] };
+
+    UciParseError::new(kind, line, column, span.as_str(), expected)
+}
 
-    0
+/// Returns the square inside a `from_sq` or `to_sq` pair.
+fn parse_inner_square(sq_pair: Pair<Rule>) -> Result<Pair<Rule>, UciParseError> {
+    let span = sq_pair.as_span();
+
+    sq_pair
+        .into_inner()
+        .next()
+        .ok_or_else(|| parse_error(span, UciParseErrorKind::BadMove, "a square"))
 }
 
 #[cfg(not(feature = "chess"))]
-fn parse_a_move(sp: Pair<Rule>) -> UciMove {
+fn parse_a_move(sp: Pair<Rule>) -> Result<UciMove, UciParseError> {
     let mut from_sq = UciSquare::default();
     let mut to_sq = UciSquare::default();
     let mut promotion: Option<UciPiece> = None;
 
     for move_token in sp.into_inner() {
         match move_token.as_rule() {
+            Rule::null_move => {
+                return Ok(UciMove::null());
+            }
             Rule::from_sq => {
-                from_sq = parse_square(move_token.into_inner().next().unwrap());
+                from_sq = parse_square(parse_inner_square(move_token)?)?;
             }
             Rule::to_sq => {
-                to_sq = parse_square(move_token.into_inner().next().unwrap());
+                to_sq = parse_square(parse_inner_square(move_token)?)?;
             }
             Rule::promotion => {
-                promotion = Some(UciPiece::from_str(move_token.as_span().as_str()).unwrap());
+                let span = move_token.as_span();
+                promotion = Some(
+                    UciPiece::from_str(span.as_str())
+                        .map_err(|_| parse_error(span, UciParseErrorKind::BadMove, "a promotion piece"))?,
+                );
             }
-            _ => unreachable!(),
+            _ => {}
         }
     }
 
-    UciMove {
+    Ok(UciMove {
         from: from_sq,
         to: to_sq,
         promotion,
-    }
+    })
 }
 
 #[cfg(feature = "chess")]
-fn parse_a_move(sp: Pair<Rule>) -> ChessMove {
+fn parse_a_move(sp: Pair<Rule>) -> Result<ChessMove, UciParseError> {
     let mut from_sq = Square::default();
     let mut to_sq = Square::default();
     let mut promotion: Option<Piece> = None;
 
     for move_token in sp.into_inner() {
         match move_token.as_rule() {
+            Rule::null_move => {
+                return Ok(null_move());
+            }
             Rule::from_sq => {
-                from_sq = parse_square(move_token.into_inner().next().unwrap());
+                from_sq = parse_square(parse_inner_square(move_token)?)?;
             }
             Rule::to_sq => {
-                to_sq = parse_square(move_token.into_inner().next().unwrap());
+                to_sq = parse_square(parse_inner_square(move_token)?)?;
             }
             Rule::promotion => {
-                promotion = Some(piece_from_str(move_token.as_span().as_str()).unwrap());
+                let span = move_token.as_span();
+                promotion = Some(
+                    piece_from_str(span.as_str())
+                        .map_err(|_| parse_error(span, UciParseErrorKind::BadMove, "a promotion piece"))?,
+                );
             }
-            _ => unreachable!(),
+            _ => {}
         }
     }
 
-    ChessMove::new(from_sq, to_sq, promotion)
+    Ok(ChessMove::new(from_sq, to_sq, promotion))
 }
 
 #[cfg(feature = "chess")]
@@ -983,7 +1483,7 @@ fn piece_from_str(s: &str) -> Result<Piece, FmtError> {
 mod tests {
     use std::io::*;
 
-    use crate::uci::Serializable;
+    use crate::uci::{Serializable, UciFen, UciInfoAttribute, UciOptionConfig};
 
     use super::*;
 
@@ -1019,7 +1519,7 @@ mod tests {
     #[test]
     fn test_debug_wrong_param() {
         let ml = parse_strict("debug abc\r\n");
-        assert_eq!(ml.is_err(), true);
+        assert!(ml.is_err());
     }
 
     #[test]
@@ -1044,9 +1544,9 @@ mod tests {
             UciMessage::SetOption { name, value } => {
                 assert_eq!(*name, String::from("Nullmove"));
                 let val = value.clone();
-                assert_eq!(val.is_some(), true);
+                assert!(val.is_some());
                 assert_eq!(val.unwrap().as_str(), String::from("true"));
-                assert_eq!(so.as_bool().unwrap(), true);
+                assert!(so.as_bool().unwrap());
             }
             _ => unreachable!(),
         }
@@ -1063,9 +1563,9 @@ mod tests {
             UciMessage::SetOption { name, value } => {
                 assert_eq!(*name, String::from("Selectivity is awesome"));
                 let val = value.clone();
-                assert_eq!(val.is_some(), true);
+                assert!(val.is_some());
                 assert_eq!(val.unwrap().as_str(), String::from("3"));
-                assert_eq!(so.as_bool().is_none(), true);
+                assert!(so.as_bool().is_none());
                 assert_eq!(so.as_i32().unwrap(), 3);
             }
             _ => unreachable!(),
@@ -1083,7 +1583,7 @@ mod tests {
             UciMessage::SetOption { name, value } => {
                 assert_eq!(*name, String::from("Clear Hash"));
                 let val = value.clone();
-                assert_eq!(val.is_some(), false);
+                assert!(val.is_none());
             }
             _ => unreachable!(),
         }
@@ -1101,7 +1601,7 @@ mod tests {
             UciMessage::SetOption { name, value } => {
                 assert_eq!(*name, String::from("NalimovPath"));
                 let val = value.clone();
-                assert_eq!(val.is_some(), true);
+                assert!(val.is_some());
                 assert_eq!(
                     val.unwrap().as_str(),
                     String::from("c:\\chess\\tb\\4;c:\\chess\\tb\\5")
@@ -1288,6 +1788,114 @@ mod tests {
             .expect_err("Parse should fail.");
     }
 
+    #[test]
+    fn test_position_fen_without_counters() {
+        let ml = parse_strict("position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 moves e7e5\n")
+            .unwrap();
+        assert_eq!(ml.len(), 1);
+
+        match &ml[0] {
+            UciMessage::Position { fen, moves, .. } => {
+                let fen = fen.as_ref().unwrap();
+                assert_eq!(fen.as_str(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3");
+                assert!(!fen.has_halfmove_clock());
+                assert!(!fen.has_fullmove_number());
+                assert_eq!(
+                    fen.normalized().as_str(),
+                    "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+                );
+                assert_eq!(moves.len(), 1);
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(
+            ml[0].serialize(),
+            "position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 moves e7e5"
+        );
+    }
+
+    #[test]
+    fn test_position_fen_with_halfmove_clock_only() {
+        let msg = parse_one("position fen 8/8/4k3/8/8/4K3/8/8 w - - 12");
+
+        match &msg {
+            UciMessage::Position { fen, moves, .. } => {
+                let fen = fen.as_ref().unwrap();
+                assert!(fen.has_halfmove_clock());
+                assert!(!fen.has_fullmove_number());
+                assert_eq!(fen.halfmove_clock(), 12);
+                assert_eq!(fen.fullmove_number(), 1);
+                assert!(moves.is_empty());
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(msg.serialize(), "position fen 8/8/4k3/8/8/4K3/8/8 w - - 12");
+    }
+
+    #[test]
+    fn test_position_shredder_fen() {
+        let ml = parse_strict("position fen bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w GEge - 0 1 moves f1g1\n")
+            .unwrap();
+        assert_eq!(ml.len(), 1);
+
+        match &ml[0] {
+            UciMessage::Position { fen, .. } => {
+                assert!(fen.as_ref().unwrap().has_valid_castling_rights());
+            }
+            _ => unreachable!(),
+        }
+
+        assert_eq!(
+            ml[0].convert_castling(CastlingMode::Standard).serialize(),
+            "position fen bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w GEge - 0 1 moves f1g1"
+        );
+    }
+
+    #[test]
+    fn test_position_invalid_castling() {
+        parse_strict("position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1\n")
+            .expect_err("Parse should fail.");
+    }
+
+    #[test]
+    fn test_parse_with_castling_mode() {
+        let ml = parse_with_castling_mode(
+            "bestmove e1g1\n\
+            position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves e1c1\n\
+            go searchmoves e8g8 e8d8\n\
+            info depth 3 currmove e8g8 pv e8c8 d1d8 refutation e8g8 d1d8 currline 1 e8c8\n\
+            bestmove e8g8 ponder d1d8\n\
+            position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves e1c1 e8g8\n\
+            bestmove c1b1\n",
+            CastlingMode::Chess960,
+        );
+
+        let serialized: Vec<String> = ml.iter().map(|m| m.serialize()).collect();
+        assert_eq!(
+            serialized,
+            vec![
+                "bestmove e1g1",
+                "position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves e1a1",
+                "go  searchmoves e8h8 e8d8 ",
+                "info depth 3 currmove e8h8 pv e8a8 d1d8 refutation e8h8 d1d8 currline cpunr 1 e8a8",
+                "bestmove e8h8 ponder d1d8",
+                "position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves e1a1 e8h8",
+                "bestmove c1b1",
+            ]
+        );
+
+        let config = ParserConfig::default().castling_mode(Some(CastlingMode::Standard));
+        let input = "position fen r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1\nbestmove e8h8 ponder e1a1\n";
+        let expected = vec!["position fen r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "bestmove e8g8 ponder e1c1"];
+
+        let ml = parse_with_config(input, &config).unwrap();
+        assert_eq!(ml.iter().map(|m| m.serialize()).collect::<Vec<String>>(), expected);
+        let ml = parse_bytes_with_config(input.as_bytes(), &config).unwrap();
+        assert_eq!(ml.iter().map(|m| m.serialize()).collect::<Vec<String>>(), expected);
+    }
+
     #[test]
     fn test_position_startpos_no_moves() {
         let ml = parse_strict("position   startpos\r\n").unwrap();
@@ -1341,7 +1949,7 @@ mod tests {
 
         assert_eq!(
             ml[0],
-            UciMessage::go_movetime(Duration::milliseconds(55055))
+            UciMessage::go_movetime(UciDuration::milliseconds(55055))
         );
     }
 
@@ -1351,12 +1959,13 @@ mod tests {
             .unwrap();
         assert_eq!(ml.len(), 1);
 
-        let tl = UciTimeControl::TimeLeft {
-            white_time: Some(Duration::milliseconds(903000)),
-            black_time: Some(Duration::milliseconds(770908)),
-            white_increment: Some(Duration::milliseconds(15000)),
-            black_increment: Some(Duration::milliseconds(10000)),
+        let tl = UciTimeControl {
+            white_time: Some(UciDuration::milliseconds(903000)),
+            black_time: Some(UciDuration::milliseconds(770908)),
+            white_increment: Some(UciDuration::milliseconds(15000)),
+            black_increment: Some(UciDuration::milliseconds(10000)),
             moves_to_go: Some(17),
+            ..UciTimeControl::default()
         };
 
         assert_eq!(
@@ -1374,13 +1983,60 @@ mod tests {
         assert_eq!(ml.len(), 1);
 
         let result = UciMessage::Go {
-            time_control: Some(UciTimeControl::Ponder),
+            time_control: Some(UciTimeControl::ponder()),
             search_control: Some(UciSearchControl::depth(6)),
         };
 
         assert_eq!(ml[0], result);
     }
 
+    #[test]
+    fn test_go_ponder_with_clock() {
+        let ml = parse_strict("go ponder wtime 60000 btime 60000 winc 1000 binc 1000\n").unwrap();
+        assert_eq!(ml.len(), 1);
+
+        let tc = UciTimeControl {
+            ponder: true,
+            white_time: Some(UciDuration::milliseconds(60000)),
+            black_time: Some(UciDuration::milliseconds(60000)),
+            white_increment: Some(UciDuration::milliseconds(1000)),
+            black_increment: Some(UciDuration::milliseconds(1000)),
+            ..UciTimeControl::default()
+        };
+
+        assert_eq!(
+            ml[0],
+            UciMessage::Go {
+                time_control: Some(tc),
+                search_control: None,
+            }
+        );
+        assert_eq!(parse_one(&ml[0].serialize()), ml[0]);
+    }
+
+    #[test]
+    fn test_go_infinite_with_clock() {
+        let ml = parse_strict("go wtime 1000 infinite btime 2000 movetime 500\n").unwrap();
+        assert_eq!(ml.len(), 1);
+
+        let tc = UciTimeControl {
+            infinite: true,
+            white_time: Some(UciDuration::milliseconds(1000)),
+            black_time: Some(UciDuration::milliseconds(2000)),
+            move_time: Some(UciDuration::milliseconds(500)),
+            ..UciTimeControl::default()
+        };
+        assert!(tc.has_clock());
+
+        assert_eq!(
+            ml[0],
+            UciMessage::Go {
+                time_control: Some(tc),
+                search_control: None,
+            }
+        );
+    }
+
     #[test]
     fn test_search_control_mate() {
         let ml = parse_strict("go mate 12\n").unwrap();
@@ -1441,7 +2097,7 @@ mod tests {
             parse_strict("go movetime 10000 searchmoves a1h8 depth 6 nodes 55000000\n").unwrap();
         assert_eq!(ml.len(), 1);
 
-        let tc = UciTimeControl::MoveTime(Duration::milliseconds(10000));
+        let tc = UciTimeControl::move_time(UciDuration::milliseconds(10000));
 
         #[cfg(not(feature = "chess"))]
         let sc = UciSearchControl {
@@ -1556,39 +2212,99 @@ mod tests {
         let m = UciMessage::BestMove {
             best_move: ChessMove::new(Square::G1, Square::F3, None),
 
-            ponder: None,
+            ponder: None,
+        };
+
+        assert_eq!(m, ml[0]);
+    }
+
+    // bestmove g1f3 ponder d8f6
+    #[test]
+    fn test_bestmove_with_ponder() {
+        let ml = parse_strict("bestmove g1f3 ponder d8f6\n").unwrap();
+        assert_eq!(ml.len(), 1);
+
+        #[cfg(not(feature = "chess"))]
+        let m = UciMessage::BestMove {
+            best_move: UciMove {
+                from: UciSquare::from('g', 1),
+                to: UciSquare::from('f', 3),
+                promotion: None,
+            },
+
+            ponder: Some(UciMove {
+                from: UciSquare::from('d', 8),
+                to: UciSquare::from('f', 6),
+                promotion: None,
+            }),
+        };
+
+        #[cfg(feature = "chess")]
+        let m = UciMessage::BestMove {
+            best_move: ChessMove::new(Square::G1, Square::F3, None),
+
+            ponder: Some(ChessMove::new(Square::D8, Square::F6, None)),
+        };
+
+        assert_eq!(m, ml[0]);
+    }
+
+    #[test]
+    fn test_bestmove_null() {
+        let ml = parse_strict("bestmove 0000\nbestmove (none)\n").unwrap();
+        assert_eq!(ml.len(), 2);
+
+        #[cfg(not(feature = "chess"))]
+        let m = UciMessage::best_move(UciMove::null());
+
+        #[cfg(feature = "chess")]
+        let m = UciMessage::best_move(null_move());
+
+        assert_eq!(m, ml[0]);
+        assert_eq!(m, ml[1]);
+        assert_eq!(ml[1].serialize(), "bestmove 0000");
+    }
+
+    #[test]
+    fn test_position_null_move() {
+        let ml = parse_strict("position startpos moves e2e4 0000 d2d4\n").unwrap();
+        assert_eq!(ml.len(), 1);
+
+        #[cfg(not(feature = "chess"))]
+        let moves = vec![
+            UciMove::from_to(UciSquare::from('e', 2), UciSquare::from('e', 4)),
+            UciMove::null(),
+            UciMove::from_to(UciSquare::from('d', 2), UciSquare::from('d', 4)),
+        ];
+
+        #[cfg(feature = "chess")]
+        let moves = vec![
+            ChessMove::new(Square::E2, Square::E4, None),
+            null_move(),
+            ChessMove::new(Square::D2, Square::D4, None),
+        ];
+
+        let pos = UciMessage::Position {
+            startpos: true,
+            fen: None,
+            moves,
         };
 
-        assert_eq!(m, ml[0]);
+        assert_eq!(ml[0], pos);
+        assert_eq!(ml[0].serialize(), "position startpos moves e2e4 0000 d2d4");
     }
 
-    // bestmove g1f3 ponder d8f6
     #[test]
-    fn test_bestmove_with_ponder() {
-        let ml = parse_strict("bestmove g1f3 ponder d8f6\n").unwrap();
-        assert_eq!(ml.len(), 1);
+    fn test_info_currline_null_move() {
+        let ml = parse_strict("info currline 0000\n").unwrap();
 
         #[cfg(not(feature = "chess"))]
-        let m = UciMessage::BestMove {
-            best_move: UciMove {
-                from: UciSquare::from('g', 1),
-                to: UciSquare::from('f', 3),
-                promotion: None,
-            },
-
-            ponder: Some(UciMove {
-                from: UciSquare::from('d', 8),
-                to: UciSquare::from('f', 6),
-                promotion: None,
-            }),
-        };
+        let line = vec![UciMove::null()];
 
         #[cfg(feature = "chess")]
-        let m = UciMessage::BestMove {
-            best_move: ChessMove::new(Square::G1, Square::F3, None),
+        let line = vec![null_move()];
 
-            ponder: Some(ChessMove::new(Square::D8, Square::F6, None)),
-        };
+        let m = UciMessage::Info(vec![UciInfoAttribute::CurrLine { cpu_nr: None, line }]);
 
         assert_eq!(m, ml[0]);
     }
@@ -1829,7 +2545,7 @@ mod tests {
     fn test_parse_info_time() {
         let ml = parse_strict("info    time    9002\n").unwrap();
 
-        let m = UciMessage::Info(vec![UciInfoAttribute::Time(Duration::milliseconds(9002))]);
+        let m = UciMessage::Info(vec![UciInfoAttribute::Time(UciDuration::milliseconds(9002))]);
 
         assert_eq!(m, ml[0]);
     }
@@ -1852,6 +2568,27 @@ mod tests {
         assert_eq!(m, ml[0]);
     }
 
+    #[test]
+    fn test_parse_info_currmovenumber() {
+        let ml = parse_strict("info depth 12 currmovenumber 3 currmove e2e4 nodes 1000\n").unwrap();
+
+        #[cfg(not(feature = "chess"))]
+        let curr_move = UciMove::from_to(UciSquare::from('e', 2), UciSquare::from('e', 4));
+
+        #[cfg(feature = "chess")]
+        let curr_move = ChessMove::new(Square::E2, Square::E4, None);
+
+        let m = UciMessage::Info(vec![
+            UciInfoAttribute::Depth(12),
+            UciInfoAttribute::CurrMoveNum(3),
+            UciInfoAttribute::CurrMove(curr_move),
+            UciInfoAttribute::Nodes(1000),
+        ]);
+
+        assert_eq!(m, ml[0]);
+        assert_eq!(m.serialize(), "info depth 12 currmovenumber 3 currmove e2e4 nodes 1000");
+    }
+
     #[test]
     fn test_parse_info_hashfull() {
         let ml = parse_strict("info hashfull 673\n").unwrap();
@@ -1929,6 +2666,38 @@ mod tests {
         assert_eq!(m, ml[0]);
     }
 
+    #[test]
+    fn test_parse_info_any_resync() {
+        let ml = parse_strict("info depth 10 ebf 1.8 nodes 1234 hashes 1 2 3 seldepth 12 vendorflag\n").unwrap();
+
+        let m = UciMessage::Info(vec![
+            UciInfoAttribute::Depth(10),
+            UciInfoAttribute::Any("ebf".to_owned(), "1.8".to_owned()),
+            UciInfoAttribute::Nodes(1234),
+            UciInfoAttribute::Any("hashes".to_owned(), "1 2 3".to_owned()),
+            UciInfoAttribute::SelDepth(12),
+            UciInfoAttribute::Any("vendorflag".to_owned(), String::new()),
+        ]);
+
+        assert_eq!(m, ml[0]);
+        assert_eq!(m.serialize(), "info depth 10 ebf 1.8 nodes 1234 hashes 1 2 3 seldepth 12 vendorflag");
+    }
+
+    #[test]
+    fn test_parse_info_any_consecutive() {
+        let ml = parse_strict("info depth 10 ebf 1.8 foo 3 nodes 1234\ninfo string\n").unwrap();
+
+        let m = UciMessage::Info(vec![
+            UciInfoAttribute::Depth(10),
+            UciInfoAttribute::Any("ebf".to_owned(), "1.8 foo 3".to_owned()),
+            UciInfoAttribute::Nodes(1234),
+        ]);
+
+        assert_eq!(m, ml[0]);
+        assert_eq!(ml[1], UciMessage::Info(vec![UciInfoAttribute::String(String::new())]));
+        assert_eq!(ml[1].serialize(), "info string");
+    }
+
     #[test]
     fn test_parse_info_currmove() {
         let ml = parse_strict("info currmove a7a8q\n").unwrap();
@@ -2141,6 +2910,31 @@ mod tests {
         assert_eq!(m, ml[0]);
     }
 
+    #[test]
+    fn test_info_wdl() {
+        let ml = parse_strict("info depth 20 score cp 35 wdl 412 530 58 nodes 1234\n").unwrap();
+
+        let m = UciMessage::Info(vec![
+            UciInfoAttribute::Depth(20),
+            UciInfoAttribute::from_centipawns(35),
+            UciInfoAttribute::Wdl {
+                win: 412,
+                draw: 530,
+                loss: 58,
+            },
+            UciInfoAttribute::Nodes(1234),
+        ]);
+
+        assert_eq!(m, ml[0]);
+    }
+
+    #[test]
+    fn test_info_wdl_out_of_range() {
+        let ml = parse_with_unknown("info depth 20 wdl 70000 530 58\n");
+        assert_eq!(ml.len(), 1);
+        assert!(ml[0].is_unknown());
+    }
+
     // info score cp 13  depth 1 nodes 13 time 15 pv f1b5
     #[test]
     fn test_info_multi1() {
@@ -2153,7 +2947,7 @@ mod tests {
             UciInfoAttribute::from_centipawns(13),
             UciInfoAttribute::Depth(1),
             UciInfoAttribute::Nodes(13),
-            UciInfoAttribute::Time(Duration::milliseconds(15)),
+            UciInfoAttribute::Time(UciDuration::milliseconds(15)),
             UciInfoAttribute::Pv(vec![UciMove::from_to(
                 UciSquare::from('f', 1),
                 UciSquare::from('b', 5),
@@ -2165,7 +2959,7 @@ mod tests {
             UciInfoAttribute::from_centipawns(13),
             UciInfoAttribute::Depth(1),
             UciInfoAttribute::Nodes(13),
-            UciInfoAttribute::Time(Duration::milliseconds(15)),
+            UciInfoAttribute::Time(UciDuration::milliseconds(15)),
             UciInfoAttribute::Pv(vec![ChessMove::new(Square::F1, Square::B5, None)]),
         ]);
 
@@ -2206,7 +3000,7 @@ mod tests {
             UciInfoAttribute::from_centipawns(20),
             UciInfoAttribute::Depth(3),
             UciInfoAttribute::Nodes(423),
-            UciInfoAttribute::Time(Duration::milliseconds(15)),
+            UciInfoAttribute::Time(UciDuration::milliseconds(15)),
             UciInfoAttribute::Pv(vec![
                 UciMove::from_to(UciSquare::from('f', 1), UciSquare::from('c', 4)),
                 UciMove::from_to(UciSquare::from('g', 8), UciSquare::from('f', 6)),
@@ -2219,7 +3013,7 @@ mod tests {
             UciInfoAttribute::from_centipawns(20),
             UciInfoAttribute::Depth(3),
             UciInfoAttribute::Nodes(423),
-            UciInfoAttribute::Time(Duration::milliseconds(15)),
+            UciInfoAttribute::Time(UciDuration::milliseconds(15)),
             UciInfoAttribute::Pv(vec![
                 ChessMove::new(Square::F1, Square::C4, None),
                 ChessMove::new(Square::G8, Square::F6, None),
@@ -2239,7 +3033,7 @@ mod tests {
     fn test_parse_with_unknown() {
         let ml = parse_with_unknown("not really a message\n");
         assert_eq!(1, ml.len());
-        assert_eq!(ml[0].is_unknown(), true);
+        assert!(ml[0].is_unknown());
 
         match &ml[0] {
             UciMessage::Unknown(msg, _) => {
@@ -2253,8 +3047,8 @@ mod tests {
     fn test_parse_with_unknown_success() {
         let ml = parse_with_unknown("uci\nuciok\n");
         assert_eq!(2, ml.len());
-        assert_eq!(ml[0].is_unknown(), false);
-        assert_eq!(ml[1].is_unknown(), false);
+        assert!(!ml[0].is_unknown());
+        assert!(!ml[1].is_unknown());
 
         assert_eq!(ml, vec![UciMessage::Uci, UciMessage::UciOk]);
     }
@@ -2354,7 +3148,7 @@ mod tests {
         assert_eq!(msgs[0], UciMessage::Uci);
         assert_eq!(msgs[1], UciMessage::Debug(true));
         assert_eq!(msgs[2], UciMessage::UciNewGame);
-        assert_eq!(msgs[3], UciMessage::Unknown("abc".to_string(), None));
+        assert!(matches!(&msgs[3], UciMessage::Unknown(s, Some(e)) if s == "abc" && (e.line, e.column) == (4, 1)));
         assert_eq!(msgs[4], UciMessage::Stop);
         assert_eq!(msgs[5], UciMessage::Quit);
     }
@@ -2364,13 +3158,42 @@ mod tests {
         let msgs = parse_with_unknown("I am the walrus\nuci\ndebug on\nShould I stay \
         or should I go?\nLondon calling\nquit\nAre we there yet?\n");
         assert_eq!(msgs.len(), 7);
-        assert_eq!(msgs[0], UciMessage::Unknown("I am the walrus".to_string(), None));
         assert_eq!(msgs[1], UciMessage::Uci);
         assert_eq!(msgs[2], UciMessage::Debug(true));
-        assert_eq!(msgs[3], UciMessage::Unknown("Should I stay or should I go?".to_string(), None));
-        assert_eq!(msgs[4], UciMessage::Unknown("London calling".to_string(), None));
         assert_eq!(msgs[5], UciMessage::Quit);
-        assert_eq!(msgs[6], UciMessage::Unknown("Are we there yet?".to_string(), None));
+
+        let unknown = [(0, "I am the walrus", 1), (3, "Should I stay or should I go?", 4), (4, "London calling", 5),
+            (6, "Are we there yet?", 7)];
+        for (i, text, line) in unknown.iter() {
+            match &msgs[*i] {
+                UciMessage::Unknown(s, Some(e)) => {
+                    assert_eq!(s, text);
+                    assert_eq!((e.kind, e.line, e.column), (UciParseErrorKind::UnknownCommand, *line, 1));
+                }
+                m => panic!("Expected UciMessage::Unknown with an error, got {:?}", m),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_with_unknown_spanned_errors() {
+        let input = "uci
+  foo bar
+isready
+go depth 5 movestogo 70000
+";
+        let spanned = parse_with_unknown_spanned(input);
+        let by_line = parse_with_config_spanned(input, &ParserConfig::default()).unwrap();
+
+        assert_eq!(spanned.len(), 4);
+        assert_eq!(spanned, by_line);
+        match &spanned[1].value {
+            UciMessage::Unknown(s, Some(e)) => {
+                assert_eq!(s, "foo bar");
+                assert_eq!((e.kind, e.line, e.column), (UciParseErrorKind::UnknownCommand, 2, 3));
+            }
+            m => panic!("Expected UciMessage::Unknown with an error, got {:?}", m),
+        }
     }
 
     #[test]
@@ -2471,12 +3294,13 @@ mod tests {
     fn test_parse_negative_duration_wtime() {
         let parsed_msg = parse_one("go wtime -4061 btime 56826 movestogo 90\n");
 
-        let time_control = UciTimeControl::TimeLeft {
-            white_time: Some(Duration::milliseconds(-4061)),
-            black_time: Some(Duration::milliseconds(56826)),
+        let time_control = UciTimeControl {
+            white_time: Some(UciDuration::milliseconds(-4061)),
+            black_time: Some(UciDuration::milliseconds(56826)),
             white_increment: None,
             black_increment: None,
             moves_to_go: Some(90),
+            ..UciTimeControl::default()
         };
 
         let test_msg = UciMessage::Go {
@@ -2491,12 +3315,13 @@ mod tests {
     fn test_parse_signed_positive_duration_wtime() {
         let parsed_msg = parse_one("go wtime +15030 btime +56826 movestogo 90\n");
 
-        let time_control = UciTimeControl::TimeLeft {
-            white_time: Some(Duration::milliseconds(15030)),
-            black_time: Some(Duration::milliseconds(56826)),
+        let time_control = UciTimeControl {
+            white_time: Some(UciDuration::milliseconds(15030)),
+            black_time: Some(UciDuration::milliseconds(56826)),
             white_increment: None,
             black_increment: None,
             moves_to_go: Some(90),
+            ..UciTimeControl::default()
         };
 
         let test_msg = UciMessage::Go {
@@ -2530,15 +3355,562 @@ mod tests {
     fn test_parse_signed_improperly_duration_wtime_strict() {
         let err = parse_strict("go wtime -15030 btime x56826 movestogo 90\n");
         assert!(err.is_err());
-        let e: pest::error::Error<_> = err.unwrap_err();
-        match e.variant {
-            pest::error::ErrorVariant::ParsingError {
-                positives,
-                negatives: _,
-            } => {
-                assert!(!positives.is_empty());
+        let e: UciParseError = err.unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::Syntax);
+        assert_eq!((e.line, e.column), (1, 23));
+        assert!(!e.expected.is_empty());
+    }
+
+    #[test]
+    fn test_try_parse_one() {
+        assert_eq!(try_parse_one("uci\n"), Ok(UciMessage::Uci));
+
+        let e = try_parse_one("  abc def").unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::UnknownCommand);
+        assert_eq!((e.line, e.column), (1, 3));
+        assert_eq!(e.text, "abc");
+        assert!(e.expected.contains(&"isready".to_string()));
+
+        let e = try_parse_one("position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1").unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::BadFen);
+        assert_eq!(e.column, 62);
+
+        let e = try_parse_one("go searchmoves e2e9").unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::BadMove);
+        assert_eq!(e.to_string(), "bad move at line 1, column 19: `9` (expected a rank)");
+
+        let e = try_parse_one("position fen rnbqkbnr/ppppXppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap_err();
+        assert_eq!(e.expected, vec!["a piece".to_string(), "a rank".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_strict_error_line() {
+        let e = parse_strict("uci\nisready\n  foo\n").unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::UnknownCommand);
+        assert_eq!((e.line, e.column), (3, 3));
+        assert_eq!(e.text, "foo");
+
+        let e = parse_strict("uci\ngo depth 5 movestogo 70000\n").unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::OutOfRange);
+        assert_eq!((e.line, e.column), (2, 22));
+
+        let oversized = [("go depth 999999", 10), ("info depth 999999", 12), ("go wtime 99999999999999999999", 10)];
+        for (input, column) in oversized.iter() {
+            let e = try_parse_one(input).unwrap_err();
+            assert_eq!(e.kind, UciParseErrorKind::OutOfRange);
+            assert_eq!((e.column, e.text.as_str()), (*column, &input[*column - 1..]));
+        }
+    }
+
+    #[test]
+    fn test_parse_with_config_unknown_policy() {
+        let input = "uci\nfoo bar\nisready\n";
+
+        let config = ParserConfig::default().unknown_messages(UnknownMessagePolicy::Ignore);
+        assert_eq!(parse_with_config(input, &config).unwrap(), vec![UciMessage::Uci, UciMessage::IsReady]);
+
+        let ml = parse_with_config(input, &ParserConfig::default()).unwrap();
+        assert_eq!(ml.len(), 3);
+        match &ml[1] {
+            UciMessage::Unknown(text, Some(e)) => {
+                assert_eq!(text, "foo bar");
+                assert_eq!(e.kind, UciParseErrorKind::UnknownCommand);
+                assert_eq!((e.line, e.column), (2, 1));
+            }
+            _ => unreachable!(),
+        }
+
+        let e = parse_with_config(input, &ParserConfig::strict()).unwrap_err();
+        assert_eq!((e.line, e.text.as_str()), (2, "foo"));
+    }
+
+    #[test]
+    fn test_parse_with_config_case_sensitive() {
+        let config = ParserConfig::default().case_sensitive(true);
+
+        assert_eq!(parse_with_config("go INFINITE\n", &ParserConfig::default()).unwrap()[0], UciMessage::go_infinite());
+        assert!(parse_with_config("go INFINITE\n", &config).unwrap()[0].is_unknown());
+        assert!(parse_with_config("Position startpos\n", &config).unwrap()[0].is_unknown());
+
+        let ml = parse_with_config(
+            "setoption name Style value Risky\nposition fen 8/8/4k3/8/8/4K3/8/8 w - - 0 1 moves e3e4\n",
+            &config,
+        )
+        .unwrap();
+        assert!(!ml[0].is_unknown());
+        assert!(!ml[1].is_unknown());
+    }
+
+    #[test]
+    fn test_parse_with_config_crlf() {
+        let config = ParserConfig::default().allow_crlf(false);
+
+        assert_eq!(parse_with_config("uci\r\n", &ParserConfig::default()).unwrap(), vec![UciMessage::Uci]);
+
+        let ml = parse_with_config("uci\r\nisready\n", &config).unwrap();
+        assert!(ml[0].is_unknown());
+        assert_eq!(ml[1], UciMessage::IsReady);
+    }
+
+    #[test]
+    fn test_parse_with_config_unknown_go_tokens() {
+        let config = ParserConfig::default().skip_unknown_go_tokens(true);
+
+        let ml = parse_with_config("go frobnicate 7 depth 5\n", &config).unwrap();
+        assert_eq!(
+            ml[0],
+            UciMessage::Go {
+                time_control: None,
+                search_control: Some(UciSearchControl::depth(5)),
+            }
+        );
+
+        let ml = parse_with_config("go frobnicate 7 depth 5\n", &ParserConfig::default()).unwrap();
+        match &ml[0] {
+            UciMessage::Unknown(_, Some(e)) => assert_eq!((e.column, e.text.as_str()), (4, "frobnicate")),
+            _ => unreachable!(),
+        }
+
+        assert!(parse_one("go frobnicate depth 5").is_unknown());
+    }
+
+    #[test]
+    fn test_parse_with_config_trailing_garbage() {
+        let config = ParserConfig::default().allow_trailing_garbage(true);
+
+        assert_eq!(parse_with_config("isready now\n", &config).unwrap(), vec![UciMessage::IsReady]);
+
+        let ml = parse_with_config("isready now\n", &ParserConfig::default()).unwrap();
+        match &ml[0] {
+            UciMessage::Unknown(_, Some(e)) => assert_eq!((e.column, e.text.as_str()), (9, "now")),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_with_config_vendor_info() {
+        let config = ParserConfig::default().keep_unknown_info_attributes(false);
+
+        let ml = parse_with_config("info depth 10 ebf 1.8 nodes 1234\n", &config).unwrap();
+        assert_eq!(
+            ml[0],
+            UciMessage::Info(vec![UciInfoAttribute::Depth(10), UciInfoAttribute::Nodes(1234)])
+        );
+
+        let ml = parse_with_config("info depth 10 ebf 1.8 nodes 1234\n", &ParserConfig::default()).unwrap();
+        match &ml[0] {
+            UciMessage::Info(attrs) => assert_eq!(attrs.len(), 3),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_with_config_castling_mode() {
+        let config = ParserConfig::default().castling_mode(Some(CastlingMode::Chess960));
+
+        let ml = parse_with_config("position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1\n", &config).unwrap();
+        assert_eq!(ml[0].serialize(), "position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1h1");
+    }
+
+    #[test]
+    fn test_parse_with_config_direction() {
+        let engine = ParserConfig::default()
+            .direction(Some(CommunicationDirection::GuiToEngine))
+            .unknown_messages(UnknownMessagePolicy::Error);
+        let gui = ParserConfig::default()
+            .direction(Some(CommunicationDirection::EngineToGui))
+            .unknown_messages(UnknownMessagePolicy::Error);
+
+        let input = "uci\nposition startpos moves e2e4\ngo infinite\n";
+        assert_eq!(parse_with_config(input, &engine).unwrap(), parse_strict(input).unwrap());
+
+        let e = parse_with_config("uci\n  bestmove e2e4\n", &engine).unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::WrongDirection);
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 3, "bestmove"));
+
+        let input = "id name Vampirc\nuciok\ninfo depth 3 pv e2e4\nbestmove e2e4\n";
+        assert_eq!(parse_with_config(input, &gui).unwrap().len(), 4);
+
+        let e = parse_with_config("isready\n", &gui).unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::WrongDirection);
+
+        let e = parse_with_config("foo\n", &gui).unwrap_err();
+        assert_eq!(e.kind, UciParseErrorKind::UnknownCommand);
+    }
+
+    #[test]
+    fn test_parse_with_config_max_line_length() {
+        let mut config = ParserConfig::default().unknown_messages(UnknownMessagePolicy::Error).max_line_length(10);
+
+        assert_eq!(parse_with_config("isready\r\nreadyok\n", &config).unwrap().len(), 2);
+
+        let e = parse_with_config("isready\nid name Vampirc\n", &config).unwrap_err();
+        assert_eq!((e.kind, e.line, e.column), (UciParseErrorKind::LineTooLong, 2, 11));
+
+        config.unknown_messages = UnknownMessagePolicy::Ignore;
+        assert_eq!(parse_with_config("isready\nid name Vampirc\n", &config).unwrap(), vec![UciMessage::IsReady]);
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let input = "uci\r\n\nid name Vampirc 0.12\nfrobnicate\ninfo depth 10 pv e2e4 e7e5\nbestmove e2e4 ponder e7e5";
+        assert_eq!(parse_bytes(input.as_bytes()), parse_with_config(input, &ParserConfig::default()).unwrap());
+
+        let ml = parse_bytes(b"id author J\xfcrgen\n\xff\xfe\x00garbage\nreadyok");
+        assert_eq!(ml.len(), 3);
+        assert_eq!(ml[0], UciMessage::id_author("J\u{fffd}rgen"));
+        assert!(matches!(ml[1], UciMessage::Unknown(ref s, Some(_)) if s.starts_with("\u{fffd}\u{fffd}")));
+        assert_eq!(ml[2], UciMessage::ReadyOk);
+
+        // The length limit applies to the raw bytes, not to the decoded text.
+        let config = ParserConfig::default().max_line_length(10);
+        let ml = parse_bytes_with_config(b"id name \xe9\xe9\r\nid name Vampirc\n", &config).unwrap();
+        assert_eq!(ml[0], UciMessage::id_name("\u{fffd}\u{fffd}"));
+        match &ml[1] {
+            UciMessage::Unknown(s, Some(e)) => {
+                assert_eq!(s, "id name Va");
+                assert_eq!((e.kind, e.line), (UciParseErrorKind::LineTooLong, 2));
+            }
+            m => panic!("Expected UciMessage::Unknown, got {:?}", m),
+        }
+    }
+
+    #[test]
+    fn test_parse_out_of_range_value() {
+        match parse_one("info depth 5 hashfull 99999999\n") {
+            UciMessage::Unknown(cmd, err) => {
+                assert_eq!(cmd, "info depth 5 hashfull 99999999");
+                let e = err.unwrap();
+                assert_eq!(e.kind, UciParseErrorKind::OutOfRange);
+                assert_eq!(e.text, "99999999");
+                assert_eq!((e.line, e.column), (1, 23));
             }
             _ => unreachable!(),
         }
+
+        assert!(parse_strict("uci\ninfo hashfull 99999999\n").is_err());
+        assert_eq!(parse("uci\ninfo hashfull 99999999\nisready\n"), vec![UciMessage::Uci, UciMessage::IsReady]);
+
+        let ml = parse_with_unknown("uci\ninfo hashfull 99999999\nisready\n");
+        assert_eq!(ml.len(), 3);
+        assert!(ml[1].is_unknown());
+        assert_eq!(ml[2], UciMessage::IsReady);
+    }
+
+    #[test]
+    fn test_parse_go_trailing_whitespace() {
+        assert_eq!(parse_one("go infinite "), UciMessage::go_infinite());
+        assert_eq!(
+            parse_one("go wtime 1 "),
+            UciMessage::Go {
+                time_control: Some(UciTimeControl {
+                    white_time: Some(UciDuration::milliseconds(1)),
+                    black_time: None,
+                    white_increment: None,
+                    black_increment: None,
+                    moves_to_go: None,
+                    ..UciTimeControl::default()
+                }),
+                search_control: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_uppercase_squares() {
+        let ml = parse_strict("position startpos moves E2E4 e7E5\n").unwrap();
+        assert_eq!(ml[0].serialize(), "position startpos moves e2e4 e7e5");
+
+        assert_eq!(parse_one("bestmove E7E8Q").serialize(), "bestmove e7e8q");
+    }
+
+    #[test]
+    fn test_parse_wide_search_limits() {
+        let msg = parse_one("go depth 300 mate -150 movestogo 1000\n");
+        assert_eq!(
+            msg,
+            UciMessage::Go {
+                time_control: Some(UciTimeControl {
+                    white_time: None,
+                    black_time: None,
+                    white_increment: None,
+                    black_increment: None,
+                    moves_to_go: Some(1000),
+                    ..UciTimeControl::default()
+                }),
+                search_control: Some(UciSearchControl {
+                    search_moves: vec![],
+                    mate: Some(-150),
+                    depth: Some(300),
+                    nodes: None,
+                }),
+            }
+        );
+        assert_eq!(parse_one(&msg.serialize()), msg);
+    }
+
+    #[test]
+    fn test_parse_info_wide_depth_and_mate() {
+        let msg = parse_one("info depth 245 seldepth 1024 score mate -300\n");
+        assert_eq!(
+            msg,
+            UciMessage::Info(vec![
+                UciInfoAttribute::Depth(245),
+                UciInfoAttribute::SelDepth(1024),
+                UciInfoAttribute::from_mate(-300),
+            ])
+        );
+        assert_eq!(msg.serialize(), "info depth 245 seldepth 1024 score mate -300");
+
+        assert!(parse_one("info score mate 40000").is_unknown());
+    }
+
+    #[test]
+    fn test_parse_never_panics() {
+        let samples = [
+            "uci\ndebug on\nisready\n",
+            "setoption name Hash value 128\n",
+            "register name Matija Kejžar code 4359874324\n",
+            "position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 moves e7e5 g1f3\n",
+            "go wtime 300000 btime 300000 winc 0 binc 0 movestogo 40 depth 20 nodes 123456 mate 3 movetime 100\n",
+            "go ponder searchmoves e2e4 d2d4 infinite\n",
+            "id name Vampirc 0.12\nid author Matija\nuciok\nreadyok\n",
+            "bestmove e7e8q ponder 0000\ncopyprotection checking\nregistration error\n",
+            "option name Style type combo default Normal var Solid var Normal var Risky\n",
+            "option name Selectivity type spin default 2 min 0 max 4\n",
+            "info depth 12 seldepth 31 time 1242 nodes 2124 nps 34928 score cp -15 lowerbound wdl 100 800 100 pv e2e4\n",
+            "info currmove e2e4 currmovenumber 1 hashfull 500 tbhits 3 sbhits 4 cpuload 9 multipv 2 refutation d1h5 g6h5\n",
+            "info currline 1 d1h5 g6h5 string some text\n",
+        ];
+        let alphabet: Vec<char> = " \n\t0123456789-+abcdefghKQkqNRBPnrbp/()wdltimeove".chars().collect();
+        let mut seed: u64 = 0x5eed_1234_abcd_ef01;
+        let mut next = |bound: usize| {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            ((seed >> 33) as usize) % bound
+        };
+
+        for sample in samples.iter() {
+            for _ in 0..300 {
+                let mut chars: Vec<char> = sample.chars().collect();
+                for _ in 0..=next(4) {
+                    let pos = next(chars.len() + 1);
+                    match next(3) {
+                        0 if pos < chars.len() => {
+                            chars.remove(pos);
+                        }
+                        1 if pos < chars.len() => chars[pos] = alphabet[next(alphabet.len())],
+                        _ => chars.insert(pos, alphabet[next(alphabet.len())]),
+                    }
+                }
+                let input: String = chars.into_iter().collect();
+
+                let _ = parse(&input);
+                let _ = parse_strict(&input);
+                let _ = parse_with_unknown(&input);
+                for line in input.lines() {
+                    let _ = parse_one(line);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_with_unknown_spanned() {
+        let input = "uci\n\n  debug on  \nabc def\r\nisready";
+        let ml = parse_with_unknown_spanned(input);
+        assert_eq!(ml.len(), 4);
+
+        assert_eq!(ml[0].value, UciMessage::Uci);
+        assert_eq!((ml[0].line, ml[0].span.clone(), ml[0].raw.as_str()), (1, 0..3, "uci"));
+
+        assert_eq!(ml[1].value, UciMessage::Debug(true));
+        assert_eq!((ml[1].line, ml[1].raw.as_str()), (3, "  debug on  "));
+        assert_eq!(&input[ml[1].span.clone()], "  debug on  ");
+        assert_eq!(&input[ml[1].trimmed_span.clone()], "debug on");
+
+        match &ml[2].value {
+            UciMessage::Unknown(text, _) => assert_eq!(text, "abc def"),
+            m => panic!("Expected UciMessage::Unknown, got {:?}", m),
+        }
+        assert_eq!((ml[2].line, ml[2].raw.as_str()), (4, "abc def\r"));
+        assert_eq!(&input[ml[2].span.clone()], "abc def\r");
+        assert_eq!(&input[ml[2].trimmed_span.clone()], "abc def");
+
+        assert_eq!(ml[3].value, UciMessage::IsReady);
+        assert_eq!((ml[3].line, ml[3].span.clone(), ml[3].trimmed_span.clone()), (5, 27..34, 27..34));
+
+        let values: MessageList = ml.into_iter().map(Spanned::into_inner).collect();
+        assert_eq!(values, parse_with_unknown(input));
+    }
+
+    #[test]
+    fn test_parse_with_config_spanned() {
+        let input = "uci\r\nfrobnicate\n\n go infinite \n";
+        let ml = parse_with_config_spanned(input, &ParserConfig::default()).unwrap();
+        assert_eq!(ml.len(), 3);
+
+        assert_eq!((ml[0].line, ml[0].span.clone(), ml[0].trimmed_span.clone()), (1, 0..4, 0..3));
+        assert_eq!((ml[1].line, ml[1].raw.as_str()), (2, "frobnicate"));
+        match &ml[1].value {
+            UciMessage::Unknown(_, Some(e)) => assert_eq!(e.line, ml[1].line),
+            m => panic!("Expected UciMessage::Unknown, got {:?}", m),
+        }
+        assert_eq!(ml[2].value, UciMessage::go_infinite());
+        assert_eq!((ml[2].line, ml[2].raw.as_str()), (4, " go infinite "));
+        assert_eq!(&input[ml[2].span.clone()], " go infinite ");
+        assert_eq!(&input[ml[2].trimmed_span.clone()], "go infinite");
+    }
+
+    #[test]
+    fn test_parse_with_errors() {
+        let input = "uci\ngo depth 10 frobnicate\n\ninfo hashfull 99999999\nisready\nposition fen 8/8 w - -\nquit";
+        let (ml, errors) = parse_with_errors(input, &ParserConfig::default());
+        assert_eq!(ml, vec![UciMessage::Uci, UciMessage::IsReady, UciMessage::Quit]);
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].line, 2);
+        assert_eq!((errors[1].kind, errors[1].line, errors[1].column), (UciParseErrorKind::OutOfRange, 4, 15));
+        assert_eq!((errors[2].kind, errors[2].line), (UciParseErrorKind::BadFen, 6));
+
+        let (ml, errors) = parse_with_errors("uci\nisready\n", &ParserConfig::default());
+        assert_eq!(ml.len(), 2);
+        assert!(errors.is_empty());
+
+        let config = ParserConfig::default().skip_unknown_go_tokens(true).unknown_messages(UnknownMessagePolicy::Error);
+        let (ml, errors) = parse_with_errors(input, &config);
+        assert_eq!(ml.len(), 4);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_one_ref_borrows_from_input() {
+        let line = "setoption name Skill Level value 20";
+        match parse_one_ref(line) {
+            UciMessageRef::SetOption { name, value } => {
+                assert_eq!((name, value), ("Skill Level", Some("20")));
+                let range = line.as_ptr() as usize..line.as_ptr() as usize + line.len();
+                assert!(range.contains(&(name.as_ptr() as usize)));
+            }
+            m => panic!("Expected UciMessageRef::SetOption, got {:?}", m),
+        }
+
+        let line = "position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 moves e7e5  g1f3";
+        match parse_one_ref(line) {
+            UciMessageRef::Position { startpos, fen, moves } => {
+                assert!(!startpos);
+                assert_eq!(fen, Some("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"));
+                assert_eq!(moves.as_str(), "e7e5  g1f3");
+                assert_eq!(moves.len(), 2);
+            }
+            m => panic!("Expected UciMessageRef::Position, got {:?}", m),
+        }
+
+        match parse_one_ref("frobnicate") {
+            UciMessageRef::Unknown(text, Some(e)) => {
+                assert_eq!(text, "frobnicate");
+                assert_eq!(e.kind, UciParseErrorKind::UnknownCommand);
+            }
+            m => panic!("Expected UciMessageRef::Unknown, got {:?}", m),
+        }
+        assert!(try_parse_one_ref("bestmove e2e9").is_err());
+    }
+
+    #[test]
+    fn test_parse_ref_to_owned_matches_parse() {
+        let input = "uci\n\
+            debug on\n\
+            register name Stefan MK code 4359874324\n\
+            register later\n\
+            position startpos moves e2e4 e7e5 e1h1\n\
+            position fen 2k5/6PR/8/8/2b4P/8/6K1/8 w - - 0 53 moves h7h8q\n\
+            setoption name Clear Hash\n\
+            go ponder wtime 60000 btime 60000 searchmoves e2e4 d2d4 depth 20\n\
+            id name Vampirc 0.12.0\n\
+            id author Matija Kejžar\n\
+            bestmove e2e4 ponder e7e5\n\
+            copyprotection checking\n\
+            option name Style type combo default Normal var Solid var Normal var Risky\n\
+            option name NalimovPath type string default <empty>\n\
+            option name Hash type spin default 16 min 1 max 1024\n\
+            option name Ponder type check default true\n\
+            info depth 20 seldepth 30 time 1234 nodes 5678 score cp -34 lowerbound multipv 2 pv e2e4 e7e5 g1f3\n\
+            info currmove e2e4 currmovenumber 1 hashfull 500 nps 1000 tbhits 1 sbhits 2 cpuload 999 wdl 300 400 300\n\
+            info refutation d1h5 g6h5 currline 1 e2e4 e7e5 ebf 1.8\n\
+            info string I like turtles\n\
+            not really a message\n\
+            quit\n";
+
+        let owned: MessageList = parse_with_unknown_ref(input).into_iter().map(UciMessageRef::into_owned).collect();
+        assert_eq!(owned, parse_with_unknown(input));
+        assert_eq!(owned.len(), 22);
+
+        for line in input.lines() {
+            assert_eq!(UciMessage::from(parse_one_ref(line)), parse_one(line));
+        }
+    }
+
+    #[test]
+    fn test_fast_path_matches_grammar() {
+        // Each line along with whether the fast path recognises it. Lines it does not recognise are left to the PEG
+        // parser, so they only need to produce the same result with and without the fast path.
+        let table = [
+            ("info depth 12 seldepth 31 time 1242 nodes 2124 score cp -15 lowerbound wdl 1 8 1 pv e2e4", true),
+            ("info depth 20 multipv 3 score mate -4 upperbound lowerbound hashfull 999 tbhits 0 pv e7e8q 0000", true),
+            ("info currmove E2E4 currmovenumber 1 currmovenum 2 sbhits 4 cpuload 9 refutation d1h5 g6h5 (none)", true),
+            ("info currline 1 2 d1h5 g6h5 string some  text\twith tabs  ", true),
+            ("info\tdepth 65535 time 999999999999 nodes 999999999999 score cp 2147483647", true),
+            ("info depth 65536 score cp 2147483648 wdl 1 2 70000", false),
+            ("info depth 12 pv e2e4 e7e9", false),
+            ("info depth 12 pv e2e4 e7e5 nodes", false),
+            ("info depth 12 ebf 1.8 nodes 1234", false),
+            ("info depth", false),
+            ("info score", false),
+            ("INFO depth 1", false),
+            ("info Depth 1", false),
+            ("info string", false),
+            ("bestmove e2e4 ponder e7e5", true),
+            ("bestmove 0000 ponder (none) ponder a7A8q", true),
+            ("bestmove a7A8q", true),
+            ("bestmove e2e4 ponder", false),
+            ("bestmove e2", false),
+            ("position startpos moves e2e4 e7e5  g1f3\tb8c6", true),
+            ("position startpos moves", false),
+            ("position startpos  moves e2e4", true),
+            ("position startpos moves e2e4 e7e5 garbage", false),
+            ("position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 moves e7e5 g1f3", true),
+            ("position fen 2k5/6PR/8/8/2b4P/8/6K1/8 w - - 0 53", true),
+            ("position fen 8/8/8/8/8/8/8/8 W HAha - 10", true),
+            ("position fen 8/8/8/8/8/8/8 w - - 0 1", false),
+            ("position fen 8/8/8/8/8/8/8/8 w - e9 0 1", false),
+            ("position fen 8/8/8/8/8/8/8/8 w - - 12345 1", false),
+            ("position", false),
+            ("isready", false),
+        ];
+        let configs = [
+            ParserConfig::default(),
+            ParserConfig::strict(),
+            ParserConfig::lenient(),
+            ParserConfig::default().direction(Some(CommunicationDirection::GuiToEngine)),
+        ];
+
+        for (line, fast) in table.iter() {
+            let grammar = do_parse_uci(line, Rule::single_message_per_line)
+                .map(|ml| ml.into_iter().next().map(|(_, m)| m));
+
+            match fast::parse_line(line, false) {
+                Some(m) => {
+                    assert!(fast, "{:?} unexpectedly took the fast path", line);
+                    assert_eq!(grammar, Ok(Some(m.clone())), "fast path differs for {:?}", line);
+                    assert_eq!(fast::parse_line(&format!("{}\r\n", line), false), Some(m));
+                }
+                None => assert!(!fast, "{:?} did not take the fast path", line),
+            }
+
+            for config in configs.iter() {
+                let slow = config.clone().fast_path(false);
+                assert_eq!(parse_line(line, config), parse_line(line, &slow), "{:?} with {:?}", line, config);
+                assert_eq!(try_parse_one_with_config(line, config), try_parse_one_with_config(line, &slow));
+            }
+        }
     }
 }
@@ -0,0 +1,180 @@
+//! The `reader` module contains `UciReader`, an iterator over the UCI messages read from a `BufRead`, such as the
+//! standard input, the output pipe of an engine process or a log file.
+
+use std::io::{BufRead, ErrorKind};
+
+use crate::config::ParserConfig;
+use crate::error::UciReadError;
+use crate::stream::UciStreamParser;
+use crate::uci::UciMessage;
+
+/// An iterator that reads a `BufRead` line by line and yields the parsed messages.
+///
+/// Lines are parsed like in `parse_with_config`, according to the `ParserConfig` the reader was created with (by
+/// default, unrecognised lines are returned as `UciMessage::Unknown`). Empty lines are skipped, lines may be
+/// terminated by either `\n` or `\r\n`, and the last line does not need a terminator. Invalid UTF-8 is replaced with
+/// `U+FFFD`, and at most `ParserConfig::max_line_length` bytes of a line are held in memory. The iterator ends at the
+/// end of the input; an I/O error is returned as `UciReadError::Io`, after which reading may be retried.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use vampirc_uci::UciReader;
+///
+/// for msg in UciReader::new(io::stdin().lock()) {
+///     println!("Received message: {}", msg.unwrap());
+/// }
+/// ```
+///
+/// ```
+/// use vampirc_uci::{UciMessage, UciReader};
+///
+/// let input: &[u8] = b"uci\r\n\nisready\n";
+/// let messages: Vec<UciMessage> = UciReader::new(input).map(Result::unwrap).collect();
+///
+/// assert_eq!(messages, vec![UciMessage::Uci, UciMessage::IsReady]);
+/// ```
+#[derive(Debug)]
+pub struct UciReader<R: BufRead> {
+    reader: R,
+    parser: UciStreamParser,
+    eof: bool,
+}
+
+impl<R: BufRead> UciReader<R> {
+    /// Creates a reader that parses the lines of `reader` with the default `ParserConfig`.
+    pub fn new(reader: R) -> UciReader<R> {
+        UciReader::with_config(reader, ParserConfig::default())
+    }
+
+    /// Creates a reader that parses the lines of `reader` according to `config`.
+    pub fn with_config(reader: R, config: ParserConfig) -> UciReader<R> {
+        UciReader {
+            reader,
+            parser: UciStreamParser::with_config(config),
+            eof: false,
+        }
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Consumes the `UciReader` and returns the underlying reader. The reader is positioned right after the last line
+    /// that was returned.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for UciReader<R> {
+    type Item = Result<UciMessage, UciReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.parser.next_message() {
+                return Some(result.map_err(UciReadError::Parse));
+            }
+
+            if self.eof {
+                return None;
+            }
+
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(UciReadError::Io(e))),
+            };
+
+            if buf.is_empty() {
+                self.parser.finish();
+                self.eof = true;
+                continue;
+            }
+
+            // Only consume up to the end of the current line, so that nothing past it is lost in `into_inner()`.
+            let len = buf.iter().position(|b| *b == b'\n').map_or(buf.len(), |pos| pos + 1);
+            self.parser.feed(&buf[..len]);
+            self.reader.consume(len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader, Read};
+
+    use crate::config::UnknownMessagePolicy;
+    use crate::error::UciParseErrorKind;
+
+    use super::*;
+
+    /// A reader that returns at most three bytes at a time and fails once with `Interrupted` and once with another
+    /// error.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        reads: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads += 1;
+            match self.reads {
+                2 => return Err(io::Error::new(ErrorKind::Interrupted, "interrupted")),
+                5 => return Err(io::Error::other("broken pipe")),
+                _ => {}
+            }
+
+            let n = self.data.len().min(buf.len()).min(3);
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_reader() {
+        let input: &[u8] = b"uci\r\n\n  \nid name Vampirc\nfrobnicate\nbestmove e2e4";
+        let messages: Vec<UciMessage> = UciReader::new(input).map(Result::unwrap).collect();
+
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0], UciMessage::Uci);
+        assert_eq!(messages[1], UciMessage::id_name("Vampirc"));
+        assert!(matches!(messages[2], UciMessage::Unknown(ref s, _) if s == "frobnicate"));
+        assert!(matches!(messages[3], UciMessage::BestMove { .. }));
+    }
+
+    #[test]
+    fn test_reader_errors() {
+        let config = ParserConfig::default().unknown_messages(UnknownMessagePolicy::Error);
+        let trickle = Trickle {
+            data: b"isready\nfoo\nuci\n",
+            reads: 0,
+        };
+        let mut reader = UciReader::with_config(BufReader::with_capacity(4, trickle), config);
+
+        assert_eq!(reader.next().unwrap().unwrap(), UciMessage::IsReady);
+        assert!(matches!(reader.next(), Some(Err(UciReadError::Io(_)))));
+        match reader.next() {
+            Some(Err(UciReadError::Parse(e))) => assert_eq!((e.kind, e.line), (UciParseErrorKind::UnknownCommand, 2)),
+            r => panic!("Expected a parse error, got {:?}", r),
+        }
+        assert_eq!(reader.next().unwrap().unwrap(), UciMessage::Uci);
+        assert!(reader.next().is_none());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_reader_into_inner() {
+        let mut reader = UciReader::new(&b"isready\nuci\n"[..]);
+        assert_eq!(reader.next().unwrap().unwrap(), UciMessage::IsReady);
+        assert_eq!(reader.into_inner(), b"uci\n");
+    }
+}
@@ -0,0 +1,211 @@
+//! Helpers for the optional `serde` support (the `serde` feature). Moves are represented as strings in the UCI long
+//! algebraic notation (`e2e4`, `e7e8q`, `0000` for the null move), both with and without the `chess` feature.
+//! Durations (`UciDuration`) are represented as a signed number of milliseconds.
+
+use alloc::vec::Vec;
+use core::fmt::{Formatter, Result as FmtResult};
+
+use serde::de::{Error as DeError, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::borrowed::parse_move_str;
+#[cfg(not(feature = "chess"))]
+use crate::uci::UciSquare;
+use crate::uci::{serialize_move, Move};
+
+/// Deserializes a move in the UCI notation.
+fn deserialize_move<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Move, D::Error> {
+    struct MoveVisitor;
+
+    impl Visitor<'_> for MoveVisitor {
+        type Value = Move;
+
+        fn expecting(&self, f: &mut Formatter) -> FmtResult {
+            write!(f, "a move in the UCI notation")
+        }
+
+        fn visit_str<E: DeError>(self, s: &str) -> Result<Move, E> {
+            parse_move_str(s).ok_or_else(|| E::invalid_value(Unexpected::Str(s), &self))
+        }
+    }
+
+    deserializer.deserialize_str(MoveVisitor)
+}
+
+/// A move wrapper used to (de)serialize the elements of move lists and optional moves.
+struct MoveString(Move);
+
+impl Serialize for MoveString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&serialize_move(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for MoveString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_move(deserializer).map(MoveString)
+    }
+}
+
+/// (De)serializes a move as a string.
+pub(crate) mod a_move {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(m: &Move, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&serialize_move(m))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Move, D::Error> {
+        deserialize_move(deserializer)
+    }
+}
+
+/// (De)serializes an optional move as a string or `null`.
+pub(crate) mod option_move {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(m: &Option<Move>, serializer: S) -> Result<S::Ok, S::Error> {
+        match m {
+            Some(m) => serializer.serialize_some(&MoveString(*m)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Move>, D::Error> {
+        Ok(Option::<MoveString>::deserialize(deserializer)?.map(|m| m.0))
+    }
+}
+
+/// (De)serializes a list of moves as a sequence of strings.
+pub(crate) mod moves {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(moves: &[Move], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(moves.iter().map(|m| MoveString(*m)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Move>, D::Error> {
+        Ok(Vec::<MoveString>::deserialize(deserializer)?.into_iter().map(|m| m.0).collect())
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+impl Serialize for Move {
+    /// Serializes the move as a string in the UCI notation, such as `e2e4`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        a_move::serialize(self, serializer)
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+impl<'de> Deserialize<'de> for Move {
+    /// Deserializes a move from a string in the UCI notation, such as `e2e4`.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_move(deserializer)
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+impl Serialize for UciSquare {
+    /// Serializes the square as a string, such as `e4`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+impl<'de> Deserialize<'de> for UciSquare {
+    /// Deserializes a square from a string, such as `e4`.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SquareVisitor;
+
+        impl Visitor<'_> for SquareVisitor {
+            type Value = UciSquare;
+
+            fn expecting(&self, f: &mut Formatter) -> FmtResult {
+                write!(f, "a square, such as e4")
+            }
+
+            fn visit_str<E: DeError>(self, s: &str) -> Result<UciSquare, E> {
+                match s.as_bytes() {
+                    [file, rank] => crate::borrowed::file_rank(*file, *rank)
+                        .map(|(file, rank)| UciSquare::from(file as char, rank + 1))
+                        .ok_or_else(|| E::invalid_value(Unexpected::Str(s), &self)),
+                    _ => Err(E::invalid_value(Unexpected::Str(s), &self)),
+                }
+            }
+        }
+
+        deserializer.deserialize_str(SquareVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{from_str, json, to_value};
+
+    use crate::parser::{parse_one, parse_with_unknown};
+    use crate::uci::UciMessage;
+
+    #[test]
+    fn test_serde_representation() {
+        let cases = [
+            ("uci", json!({"type": "uci"})),
+            ("debug on", json!({"type": "debug", "value": true})),
+            (
+                "bestmove e2e4 ponder e7e8q",
+                json!({"type": "bestmove", "value": {"best_move": "e2e4", "ponder": "e7e8q"}}),
+            ),
+            ("bestmove 0000", json!({"type": "bestmove", "value": {"best_move": "0000", "ponder": null}})),
+            (
+                "position startpos moves e2e4 e7e5",
+                json!({"type": "position", "value": {"startpos": true, "fen": null, "moves": ["e2e4", "e7e5"]}}),
+            ),
+            (
+                "info depth 3 time 50 currmovenumber 2 score cp -12 pv e2e4 e7e5",
+                json!({"type": "info", "value": [
+                    {"type": "depth", "value": 3},
+                    {"type": "time", "value": 50},
+                    {"type": "currmovenumber", "value": 2},
+                    {"type": "score", "value": {"cp": -12, "mate": null, "lower_bound": null, "upper_bound": null}},
+                    {"type": "pv", "value": ["e2e4", "e7e5"]},
+                ]}),
+            ),
+            (
+                "option name Hash type spin default 16 min 1 max 1024",
+                json!({"type": "option", "value": {
+                    "type": "spin", "name": "Hash", "default": 16, "min": 1, "max": 1024
+                }}),
+            ),
+            ("copyprotection ok", json!({"type": "copyprotection", "value": "ok"})),
+        ];
+
+        for (input, expected) in cases.iter() {
+            assert_eq!(&to_value(parse_one(input)).unwrap(), expected, "{}", input);
+        }
+
+        let go = to_value(parse_one("go ponder wtime 60000 btime 59000 movestogo 20 depth 12")).unwrap();
+        assert_eq!(go["value"]["time_control"]["white_time"], json!(60000));
+        assert_eq!(go["value"]["time_control"]["move_time"], json!(null));
+        assert_eq!(go["value"]["search_control"]["depth"], json!(12));
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let input = "uci\nid name Vampirc\nsetoption name Skill Level value 20\n\
+            position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 moves e7e5\n\
+            go wtime 1000 btime 2000 winc 10 binc 10 searchmoves e2e4 d2d4 nodes 5000\n\
+            info depth 10 seldepth 12 nps 1000 wdl 500 300 200 currmove e2e4 currline 1 e2e4 e7e5 string hi there\n\
+            info refutation d1h5 g6h5 ebf 1.8\n\
+            option name Style type combo default Normal var Solid var Normal\n\
+            registration checking\nbestmove (none)\nfrobnicate\n";
+
+        for msg in parse_with_unknown(input) {
+            let json = serde_json::to_string(&msg).unwrap();
+            assert_eq!(from_str::<UciMessage>(&json).unwrap(), msg, "{}", json);
+        }
+
+        let bad_move = r#"{"type": "bestmove", "value": {"best_move": "e2e9", "ponder": null}}"#;
+        assert!(from_str::<UciMessage>(bad_move).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! Conversions between this crate's types and the types of the [shakmaty crate](https://crates.io/crates/shakmaty)
+//! (the `shakmaty` feature). Unlike the `chess` feature, the `shakmaty` feature does not replace the crate's own
+//! representation of moves, squares and pieces. Instead, they can be converted into and from `shakmaty::uci::UciMove`,
+//! `shakmaty::Square` and `shakmaty::Role`, while a `UciFen` converts into a `shakmaty::fen::Fen`, which can be set up
+//! as a playable position. With the `chess` feature, where moves, squares and pieces are the `chess` crate's types,
+//! they are converted with functions instead (such as `chess_move_to_shakmaty`), as the orphan rules do not allow
+//! `From` implementations between the types of two other crates.
+
+use alloc::string::ToString;
+use core::convert::TryFrom;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+#[cfg(feature = "chess")]
+use chess::{ChessMove, Piece, ALL_SQUARES};
+use shakmaty::fen::{Fen, ParseFenError};
+use shakmaty::uci::UciMove as ShakmatyMove;
+use shakmaty::{Role, Square};
+#[cfg(not(feature = "chess"))]
+use shakmaty::{File, Rank};
+
+use crate::chess960::CastlingMode;
+#[cfg(feature = "chess")]
+use crate::uci::{is_null_move, null_move};
+#[cfg(not(feature = "chess"))]
+use crate::uci::{UciMove, UciPiece, UciSquare};
+use crate::uci::UciFen;
+
+/// The error returned when a value has no equivalent on the other side of a conversion to or from shakmaty: a
+/// `UciSquare` outside of the board or a move that drops a piece (`shakmaty::uci::UciMove::Put`), which is only used
+/// in the Crazyhouse variant.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct ShakmatyConversionError;
+
+impl Display for ShakmatyConversionError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "no equivalent shakmaty square or move")
+    }
+}
+
+impl core::error::Error for ShakmatyConversionError {}
+
+#[cfg(not(feature = "chess"))]
+impl TryFrom<UciSquare> for Square {
+    type Error = ShakmatyConversionError;
+
+    /// Converts a `UciSquare` into a `shakmaty::Square`. Fails if the file is not in the range of `a..h` or the rank
+    /// is not in the range of `1..8`.
+    fn try_from(square: UciSquare) -> Result<Square, ShakmatyConversionError> {
+        let file = File::from_char(square.file);
+        let rank = square.rank.checked_sub(1).and_then(|r| Rank::try_from(r).ok());
+
+        match (file, rank) {
+            (Some(file), Some(rank)) => Ok(Square::from_coords(file, rank)),
+            _ => Err(ShakmatyConversionError),
+        }
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+impl From<Square> for UciSquare {
+    fn from(square: Square) -> UciSquare {
+        UciSquare::from(square.file().char(), u8::from(square.rank()) + 1)
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+impl From<UciPiece> for Role {
+    fn from(piece: UciPiece) -> Role {
+        match piece {
+            UciPiece::Pawn => Role::Pawn,
+            UciPiece::Knight => Role::Knight,
+            UciPiece::Bishop => Role::Bishop,
+            UciPiece::Rook => Role::Rook,
+            UciPiece::Queen => Role::Queen,
+            UciPiece::King => Role::King,
+        }
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+impl From<Role> for UciPiece {
+    fn from(role: Role) -> UciPiece {
+        match role {
+            Role::Pawn => UciPiece::Pawn,
+            Role::Knight => UciPiece::Knight,
+            Role::Bishop => UciPiece::Bishop,
+            Role::Rook => UciPiece::Rook,
+            Role::Queen => UciPiece::Queen,
+            Role::King => UciPiece::King,
+        }
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+impl TryFrom<UciMove> for ShakmatyMove {
+    type Error = ShakmatyConversionError;
+
+    /// Converts a `UciMove` into a `shakmaty::uci::UciMove`, which can then be checked for legality and turned into a
+    /// playable `shakmaty::Move` with `to_move(&position)`. The null move is converted into `UciMove::Null`. Fails if
+    /// a square of a move other than the null move is outside of the board.
+    fn try_from(m: UciMove) -> Result<ShakmatyMove, ShakmatyConversionError> {
+        if m.is_null() {
+            return Ok(ShakmatyMove::Null);
+        }
+
+        Ok(ShakmatyMove::Normal {
+            from: Square::try_from(m.from)?,
+            to: Square::try_from(m.to)?,
+            promotion: m.promotion.map(Role::from),
+        })
+    }
+}
+
+#[cfg(not(feature = "chess"))]
+impl TryFrom<ShakmatyMove> for UciMove {
+    type Error = ShakmatyConversionError;
+
+    /// Converts a `shakmaty::uci::UciMove` into a `UciMove`. Fails for piece drops (`UciMove::Put`).
+    fn try_from(m: ShakmatyMove) -> Result<UciMove, ShakmatyConversionError> {
+        match m {
+            ShakmatyMove::Normal { from, to, promotion } => Ok(UciMove {
+                from: from.into(),
+                to: to.into(),
+                promotion: promotion.map(UciPiece::from),
+            }),
+            ShakmatyMove::Null => Ok(UciMove::null()),
+            ShakmatyMove::Put { .. } => Err(ShakmatyConversionError),
+        }
+    }
+}
+
+/// Converts a `chess::Square` into a `shakmaty::Square`.
+#[cfg(feature = "chess")]
+pub fn chess_square_to_shakmaty(square: chess::Square) -> Square {
+    Square::new(square.to_index() as u32)
+}
+
+/// Converts a `shakmaty::Square` into a `chess::Square`.
+#[cfg(feature = "chess")]
+pub fn chess_square_from_shakmaty(square: Square) -> chess::Square {
+    ALL_SQUARES[usize::from(square)]
+}
+
+/// Converts a `chess::Piece` into a `shakmaty::Role`.
+#[cfg(feature = "chess")]
+pub fn chess_piece_to_role(piece: Piece) -> Role {
+    match piece {
+        Piece::Pawn => Role::Pawn,
+        Piece::Knight => Role::Knight,
+        Piece::Bishop => Role::Bishop,
+        Piece::Rook => Role::Rook,
+        Piece::Queen => Role::Queen,
+        Piece::King => Role::King,
+    }
+}
+
+/// Converts a `shakmaty::Role` into a `chess::Piece`.
+#[cfg(feature = "chess")]
+pub fn chess_piece_from_role(role: Role) -> Piece {
+    match role {
+        Role::Pawn => Piece::Pawn,
+        Role::Knight => Piece::Knight,
+        Role::Bishop => Piece::Bishop,
+        Role::Rook => Piece::Rook,
+        Role::Queen => Piece::Queen,
+        Role::King => Piece::King,
+    }
+}
+
+/// Converts a `ChessMove` into a `shakmaty::uci::UciMove`, which can then be checked for legality and turned into a
+/// playable `shakmaty::Move` with `to_move(&position)`. The null move (see `null_move()`) is converted into
+/// `UciMove::Null`.
+#[cfg(feature = "chess")]
+pub fn chess_move_to_shakmaty(m: ChessMove) -> ShakmatyMove {
+    if is_null_move(&m) {
+        return ShakmatyMove::Null;
+    }
+
+    ShakmatyMove::Normal {
+        from: chess_square_to_shakmaty(m.get_source()),
+        to: chess_square_to_shakmaty(m.get_dest()),
+        promotion: m.get_promotion().map(chess_piece_to_role),
+    }
+}
+
+/// Converts a `shakmaty::uci::UciMove` into a `ChessMove`. `UciMove::Null` is converted into `null_move()`. Fails for
+/// piece drops (`UciMove::Put`).
+#[cfg(feature = "chess")]
+pub fn chess_move_from_shakmaty(m: &ShakmatyMove) -> Result<ChessMove, ShakmatyConversionError> {
+    match *m {
+        ShakmatyMove::Normal { from, to, promotion } => Ok(ChessMove::new(
+            chess_square_from_shakmaty(from),
+            chess_square_from_shakmaty(to),
+            promotion.map(chess_piece_from_role),
+        )),
+        ShakmatyMove::Null => Ok(null_move()),
+        ShakmatyMove::Put { .. } => Err(ShakmatyConversionError),
+    }
+}
+
+impl TryFrom<&UciFen> for Fen {
+    type Error = ParseFenError;
+
+    /// Parses the `UciFen` into a `shakmaty::fen::Fen`. FENs without the move counters are accepted. Use
+    /// `Fen::into_position` to set up a playable position from it.
+    fn try_from(fen: &UciFen) -> Result<Fen, ParseFenError> {
+        Fen::from_ascii(fen.as_str().as_bytes())
+    }
+}
+
+impl From<&Fen> for UciFen {
+    fn from(fen: &Fen) -> UciFen {
+        UciFen(fen.to_string())
+    }
+}
+
+impl From<CastlingMode> for shakmaty::CastlingMode {
+    fn from(mode: CastlingMode) -> shakmaty::CastlingMode {
+        match mode {
+            CastlingMode::Standard => shakmaty::CastlingMode::Standard,
+            CastlingMode::Chess960 => shakmaty::CastlingMode::Chess960,
+        }
+    }
+}
+
+impl From<shakmaty::CastlingMode> for CastlingMode {
+    fn from(mode: shakmaty::CastlingMode) -> CastlingMode {
+        match mode {
+            shakmaty::CastlingMode::Standard => CastlingMode::Standard,
+            shakmaty::CastlingMode::Chess960 => CastlingMode::Chess960,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shakmaty::{Chess, Position};
+
+    use crate::parser::parse_one;
+    use crate::uci::UciMessage;
+
+    use super::*;
+
+    #[cfg(not(feature = "chess"))]
+    #[test]
+    fn test_move_conversions() {
+        for input in ["e2e4", "a7a8q", "h2h1n", "0000"].iter() {
+            let m = match parse_one(&format!("bestmove {}", input)) {
+                UciMessage::BestMove { best_move, .. } => best_move,
+                msg => panic!("Expected UciMessage::BestMove, got {:?}", msg),
+            };
+
+            let converted = ShakmatyMove::try_from(m).unwrap();
+            assert_eq!(converted.to_string(), *input);
+            assert_eq!(UciMove::try_from(converted), Ok(m));
+        }
+
+        let off_board = UciMove::from_to(UciSquare::from('e', 2), UciSquare::from('i', 4));
+        assert_eq!(ShakmatyMove::try_from(off_board), Err(ShakmatyConversionError));
+        let drop = ShakmatyMove::Put { role: Role::Knight, to: Square::F3 };
+        assert_eq!(UciMove::try_from(drop), Err(ShakmatyConversionError));
+    }
+
+    #[cfg(not(feature = "chess"))]
+    #[test]
+    fn test_square_and_role_conversions() {
+        for square in Square::ALL.iter() {
+            let uci_square: UciSquare = (*square).into();
+            assert_eq!(uci_square.to_string(), square.to_string());
+            assert_eq!(Square::try_from(uci_square), Ok(*square));
+        }
+
+        for square in [UciSquare::default(), UciSquare::from('a', 0), UciSquare::from('a', 9), UciSquare::from('A', 1)]
+            .iter()
+        {
+            assert_eq!(Square::try_from(*square), Err(ShakmatyConversionError));
+        }
+
+        for role in Role::ALL.iter() {
+            assert_eq!(Role::from(UciPiece::from(*role)), *role);
+        }
+    }
+
+    #[cfg(feature = "chess")]
+    #[test]
+    fn test_chess_move_conversions() {
+        for input in ["e2e4", "a7a8q", "h2h1n", "0000"].iter() {
+            let m = match parse_one(&format!("bestmove {}", input)) {
+                UciMessage::BestMove { best_move, .. } => best_move,
+                msg => panic!("Expected UciMessage::BestMove, got {:?}", msg),
+            };
+
+            let converted = chess_move_to_shakmaty(m);
+            assert_eq!(converted.to_string(), *input);
+            assert_eq!(chess_move_from_shakmaty(&converted), Ok(m));
+        }
+
+        let drop = ShakmatyMove::Put { role: Role::Knight, to: Square::F3 };
+        assert_eq!(chess_move_from_shakmaty(&drop), Err(ShakmatyConversionError));
+    }
+
+    #[cfg(feature = "chess")]
+    #[test]
+    fn test_chess_square_and_piece_conversions() {
+        for square in ALL_SQUARES.iter() {
+            let converted = chess_square_to_shakmaty(*square);
+            assert_eq!(converted.to_string(), square.to_string());
+            assert_eq!(chess_square_from_shakmaty(converted), *square);
+        }
+
+        for role in Role::ALL.iter() {
+            assert_eq!(chess_piece_to_role(chess_piece_from_role(*role)), *role);
+        }
+    }
+
+    #[test]
+    fn test_fen_into_position() {
+        let msg = parse_one("position fen rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 moves e7e5");
+        let fen = match msg {
+            UciMessage::Position { fen: Some(fen), .. } => fen,
+            msg => panic!("Expected UciMessage::Position, got {:?}", msg),
+        };
+
+        let pos: Chess = Fen::try_from(&fen).unwrap().into_position(CastlingMode::Standard.into()).unwrap();
+        assert_eq!(pos.fullmoves().get(), 1);
+        assert_eq!(pos.legal_moves().len(), 20);
+
+        let round_trip = UciFen::from(&Fen::from_position(&pos, shakmaty::EnPassantMode::Always));
+        assert_eq!(round_trip.as_str(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+
+        assert!(Fen::try_from(&UciFen(String::from("rnbqkbnr/pppppppp/8 w"))).is_err());
+        assert_eq!(CastlingMode::from(shakmaty::CastlingMode::Chess960), CastlingMode::Chess960);
+    }
+}
@@ -0,0 +1,59 @@
+//! The `span` module contains the `Spanned` type, which records where in the input a parsed message came from.
+
+use alloc::string::{String, ToString};
+use core::ops::Range;
+
+/// A value (usually a `UciMessage`) together with the location in the input it was parsed from. Returned by
+/// `parse_with_unknown_spanned` and `parse_with_config_spanned`.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Spanned<T> {
+    /// The parsed value.
+    pub value: T,
+
+    /// The byte range of the whole line of the message in the input, surrounding whitespace included but the `\n`
+    /// excluded, so that `&input[span]` equals `raw`.
+    pub span: Range<usize>,
+
+    /// The byte range of the message itself in the input, without the surrounding whitespace and the `\r` of a
+    /// `\r\n` line terminator. Useful for highlighting the message.
+    pub trimmed_span: Range<usize>,
+
+    /// The line (starting with 1) on which the message starts.
+    pub line: usize,
+
+    /// The original text of the line of the message, unchanged except for the `\n` line terminator, so that it can
+    /// be forwarded as it was received.
+    pub raw: String,
+}
+
+impl<T> Spanned<T> {
+    /// Constructs a `Spanned` covering the line of `input` in `span`, which must not include the `\n`.
+    pub(crate) fn new(value: T, input: &str, span: Range<usize>, line: usize) -> Spanned<T> {
+        let raw = &input[span.clone()];
+        let start = span.start + (raw.len() - raw.trim_start().len());
+
+        Spanned {
+            value,
+            trimmed_span: start..start + raw.trim().len(),
+            span,
+            line,
+            raw: raw.to_string(),
+        }
+    }
+
+    /// Applies `f` to the value, keeping the location.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Spanned<U> {
+        Spanned {
+            value: f(self.value),
+            span: self.span,
+            trimmed_span: self.trimmed_span,
+            line: self.line,
+            raw: self.raw,
+        }
+    }
+
+    /// Discards the location and returns the value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
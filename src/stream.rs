@@ -0,0 +1,206 @@
+//! The `stream` module contains `UciStreamParser`, which parses UCI messages from input that arrives in arbitrary
+//! chunks, such as the reads from an engine's output pipe.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::chess960::CastlingTracker;
+use crate::config::ParserConfig;
+use crate::error::UciParseError;
+use crate::parser::{parse_byte_line, too_long_byte_line};
+use crate::uci::UciMessage;
+
+/// An incremental parser that is fed chunks of bytes (or `&str`s) and yields the messages of every line as soon as it
+/// is complete. Chunks may end anywhere, even in the middle of a line or of a UTF-8 sequence.
+///
+/// Each line is parsed like in `parse_with_config`, according to the `ParserConfig` the parser was created with, so
+/// depending on `ParserConfig::unknown_messages` an unrecognised line is skipped, returned as `UciMessage::Unknown` or
+/// returned as an error. Invalid UTF-8 sequences are replaced with `U+FFFD`. At most `ParserConfig::max_line_length`
+/// bytes of a line (plus a `\r`) are buffered; the rest of a longer line is discarded and the line results in a
+/// `UciParseErrorKind::LineTooLong` error.
+///
+/// # Examples
+///
+/// ```
+/// use vampirc_uci::{UciMessage, UciStreamParser};
+///
+/// let mut parser = UciStreamParser::new();
+///
+/// parser.feed(b"readyok\nid name Vamp");
+/// assert_eq!(parser.next_message(), Some(Ok(UciMessage::ReadyOk)));
+/// assert_eq!(parser.next_message(), None);
+///
+/// parser.feed("irc\r\n");
+/// assert_eq!(parser.next_message(), Some(Ok(UciMessage::id_name("Vampirc"))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct UciStreamParser {
+    config: ParserConfig,
+    buffer: Vec<u8>,
+    line_no: usize,
+    overflowed: bool,
+    castling: CastlingTracker,
+    messages: VecDeque<Result<UciMessage, UciParseError>>,
+}
+
+impl UciStreamParser {
+    /// Creates a stream parser with the default `ParserConfig`.
+    pub fn new() -> UciStreamParser {
+        UciStreamParser::with_config(ParserConfig::default())
+    }
+
+    /// Creates a stream parser that parses each line according to `config`.
+    pub fn with_config(config: ParserConfig) -> UciStreamParser {
+        UciStreamParser {
+            config,
+            buffer: Vec::new(),
+            line_no: 0,
+            overflowed: false,
+            castling: CastlingTracker::default(),
+            messages: VecDeque::new(),
+        }
+    }
+
+    /// Feeds the next chunk of input to the parser. The messages of the lines completed by this chunk can then be
+    /// obtained with `next_message()`.
+    pub fn feed<T: AsRef<[u8]>>(&mut self, chunk: T) {
+        let mut chunk = chunk.as_ref();
+
+        while let Some(pos) = chunk.iter().position(|b| *b == b'\n') {
+            self.buffer_bytes(&chunk[..pos]);
+            self.end_line();
+            chunk = &chunk[pos + 1..];
+        }
+
+        self.buffer_bytes(chunk);
+    }
+
+    /// Signals the end of the input. The last line is parsed even if it is not terminated by a newline.
+    pub fn finish(&mut self) {
+        if !self.buffer.is_empty() || self.overflowed {
+            self.end_line();
+        }
+    }
+
+    /// Returns the next parsed message (or error), or `None` if no complete line is available yet.
+    pub fn next_message(&mut self) -> Option<Result<UciMessage, UciParseError>> {
+        self.messages.pop_front()
+    }
+
+    /// Returns the number of bytes of the current, incomplete line that are being buffered.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn buffer_bytes(&mut self, bytes: &[u8]) {
+        // One extra byte for the `\r` of a `\r\n` line terminator.
+        let room = self.config.max_line_length.saturating_add(1).saturating_sub(self.buffer.len());
+
+        if bytes.len() > room {
+            self.buffer.extend_from_slice(&bytes[..room]);
+            self.overflowed = true;
+        } else {
+            self.buffer.extend_from_slice(bytes);
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.line_no += 1;
+
+        let result = if self.overflowed {
+            too_long_byte_line(&self.buffer, self.line_no, &self.config)
+        } else {
+            parse_byte_line(&self.buffer, self.line_no, &self.config, &mut self.castling)
+        };
+
+        if let Some(message) = result {
+            self.messages.push_back(message);
+        }
+
+        self.buffer.clear();
+        self.overflowed = false;
+    }
+}
+
+impl Default for UciStreamParser {
+    fn default() -> Self {
+        UciStreamParser::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::UnknownMessagePolicy;
+    use crate::error::UciParseErrorKind;
+    use crate::parser::parse_with_config;
+
+    use super::*;
+
+    fn collect(parser: &mut UciStreamParser) -> Vec<Result<UciMessage, UciParseError>> {
+        let mut v = vec![];
+        while let Some(m) = parser.next_message() {
+            v.push(m);
+        }
+        v
+    }
+
+    #[test]
+    fn test_stream_matches_parse_with_config() {
+        let input = "uci\r\n\nid name Vampirc 0.12\nfrobnicate\ninfo depth 10 pv e2e4 e7e5\nbestmove e2e4 ponder e7e5";
+        let expected: Vec<Result<UciMessage, UciParseError>> = parse_with_config(input, &ParserConfig::default())
+            .unwrap()
+            .into_iter()
+            .map(Ok)
+            .collect();
+
+        // Every possible chunk size, down to a single byte.
+        for size in 1..=input.len() {
+            let mut parser = UciStreamParser::new();
+            let mut messages = vec![];
+            for chunk in input.as_bytes().chunks(size) {
+                parser.feed(chunk);
+                messages.extend(collect(&mut parser));
+            }
+
+            assert_eq!(messages.len(), expected.len() - 1);
+            parser.finish();
+            messages.extend(collect(&mut parser));
+            assert_eq!(messages, expected);
+        }
+    }
+
+    #[test]
+    fn test_stream_split_utf8() {
+        let input = "id author Matija Kejžar\n".as_bytes();
+        let split = input.iter().position(|b| *b >= 0x80).unwrap() + 1;
+
+        let mut parser = UciStreamParser::new();
+        parser.feed(&input[..split]);
+        assert_eq!(parser.next_message(), None);
+        parser.feed(&input[split..]);
+        assert_eq!(parser.next_message(), Some(Ok(UciMessage::id_author("Matija Kejžar"))));
+
+        parser.feed(b"id name Caf\xe9\n");
+        assert_eq!(parser.next_message(), Some(Ok(UciMessage::id_name("Caf\u{fffd}"))));
+    }
+
+    #[test]
+    fn test_stream_max_line_length() {
+        let config = ParserConfig::default().unknown_messages(UnknownMessagePolicy::Error).max_line_length(16);
+        let mut parser = UciStreamParser::with_config(config);
+
+        parser.feed("info string this line is far too long");
+        assert_eq!(parser.buffered_len(), 17);
+        parser.feed(" and keeps going\nisready\n");
+
+        let e = parser.next_message().unwrap().unwrap_err();
+        assert_eq!((e.kind, e.line, e.column), (UciParseErrorKind::LineTooLong, 1, 17));
+        assert_eq!(parser.next_message(), Some(Ok(UciMessage::IsReady)));
+        assert_eq!(parser.buffered_len(), 0);
+
+        parser.feed("readyok ");
+        parser.finish();
+        assert_eq!(parser.next_message(), Some(Ok(UciMessage::ReadyOk)));
+        assert_eq!(parser.next_message(), None);
+    }
+}
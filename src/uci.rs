@@ -4,19 +4,24 @@
 //! construct them in code and then print them to the standard output to communicate with the GUI.
 
 
-use std::fmt::{Display, Error as FmtError, Formatter, Result as FmtResult};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Error as FmtError, Formatter, Result as FmtResult};
 #[cfg(not(feature = "chess"))]
-use std::str::FromStr;
+use core::str::FromStr;
 
 #[cfg(feature = "chess")]
 use chess::ChessMove;
-use chrono::Duration;
-use pest::error::Error as PestError;
 
-use crate::parser::Rule;
+use crate::chess960::{castling_rights_valid, convert_castling_moves, CastlingMode};
+use crate::duration::UciDuration;
+use crate::error::UciParseError;
+use crate::fen::FenPosition;
 
 /// Specifies whether a message is engine- or GUI-bound.
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 pub enum CommunicationDirection {
     /// An engine-bound message.
     GuiToEngine,
@@ -31,6 +36,8 @@ pub trait Serializable: Display {
 
 /// An enumeration type containing representations for all messages supported by the UCI protocol.
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "lowercase"))]
 pub enum UciMessage {
     /// The `uci` engine-bound message.
     Uci,
@@ -61,16 +68,13 @@ pub enum UciMessage {
         startpos: bool,
 
         /// The [FEN format](https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation) representation of a chess
-        /// position.
+        /// position. Only the syntax of its castling field is checked by the parser, see
+        /// `UciFen::has_valid_castling_rights()`.
         fen: Option<UciFen>,
 
         /// A list of moves to apply to the position.
-        #[cfg(not(feature = "chess"))]
-        moves: Vec<UciMove>,
-
-        /// A list of moves to apply to the position.
-        #[cfg(feature = "chess")]
-        moves: Vec<ChessMove>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::moves"))]
+        moves: Vec<Move>,
     },
 
     /// The `setoption` engine-bound message.
@@ -123,20 +127,12 @@ pub enum UciMessage {
     /// The `bestmove` GUI-bound message.
     BestMove {
         /// The move the engine thinks is the best one in the position.
-        #[cfg(not(feature = "chess"))]
-        best_move: UciMove,
-
-        /// The move the engine thinks is the best one in the position.
-        #[cfg(feature = "chess")]
-        best_move: ChessMove,
-
-        /// The move the engine would like to ponder on.
-        #[cfg(not(feature = "chess"))]
-        ponder: Option<UciMove>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::a_move"))]
+        best_move: Move,
 
         /// The move the engine would like to ponder on.
-        #[cfg(feature = "chess")]
-        ponder: Option<ChessMove>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::option_move"))]
+        ponder: Option<Move>,
     },
 
     /// The `copyprotection` GUI-bound message.
@@ -151,8 +147,8 @@ pub enum UciMessage {
     /// The `info` GUI-bound message.
     Info(Vec<UciInfoAttribute>),
 
-    /// Indicating unknown message.
-    Unknown(String, Option<PestError<Rule>>)
+    /// Indicating unknown message, together with the reason it could not be parsed (if known).
+    Unknown(String, Option<UciParseError>)
 }
 
 impl UciMessage {
@@ -186,7 +182,7 @@ impl UciMessage {
     pub fn go_ponder() -> UciMessage {
         UciMessage::Go {
             search_control: None,
-            time_control: Some(UciTimeControl::Ponder),
+            time_control: Some(UciTimeControl::ponder()),
         }
     }
 
@@ -194,16 +190,16 @@ impl UciMessage {
     pub fn go_infinite() -> UciMessage {
         UciMessage::Go {
             search_control: None,
-            time_control: Some(UciTimeControl::Infinite)
+            time_control: Some(UciTimeControl::infinite())
         }
     }
 
     /// Constructs a `go movetime <milliseconds>` [UciMessage::Register](enum.UciMessage.html#variant.Go) message, with
     /// `milliseconds` as the argument.
-    pub fn go_movetime(milliseconds: Duration) -> UciMessage {
+    pub fn go_movetime(milliseconds: UciDuration) -> UciMessage {
         UciMessage::Go {
             search_control: None,
-            time_control: Some(UciTimeControl::MoveTime(milliseconds)),
+            time_control: Some(UciTimeControl::move_time(milliseconds)),
         }
     }
 
@@ -224,8 +220,7 @@ impl UciMessage {
     }
 
     /// Constructs a `bestmove` GUI-bound message without the ponder move.
-    #[cfg(not(feature = "chess"))]
-    pub fn best_move(best_move: UciMove) -> UciMessage {
+    pub fn best_move(best_move: Move) -> UciMessage {
         UciMessage::BestMove {
             best_move,
             ponder: None,
@@ -233,35 +228,111 @@ impl UciMessage {
     }
 
     /// Constructs a `bestmove` GUI-bound message _with_ the ponder move.
-    #[cfg(not(feature = "chess"))]
-    pub fn best_move_with_ponder(best_move: UciMove, ponder: UciMove) -> UciMessage {
+    pub fn best_move_with_ponder(best_move: Move, ponder: Move) -> UciMessage {
         UciMessage::BestMove {
             best_move,
             ponder: Some(ponder),
         }
     }
 
-    /// Constructs a `bestmove` GUI-bound message without the ponder move.
-    #[cfg(feature = "chess")]
-    pub fn best_move(best_move: ChessMove) -> UciMessage {
-        UciMessage::BestMove {
-            best_move,
-            ponder: None,
-        }
+    /// Constructs an `info string ...` message.
+    pub fn info_string(s: String) -> UciMessage {
+        UciMessage::Info(vec![UciInfoAttribute::String(s)])
     }
 
-    /// Constructs a `bestmove` GUI-bound message _with_ the ponder move.
-    #[cfg(feature = "chess")]
-    pub fn best_move_with_ponder(best_move: ChessMove, ponder: ChessMove) -> UciMessage {
-        UciMessage::BestMove {
-            best_move,
-            ponder: Some(ponder),
+    /// If this `UciMessage` is a `UciMessage::Position`, returns a copy of it with all the castling moves rewritten into
+    /// the notation specified by `mode` (ie. `e1g1` in `CastlingMode::Standard` or `e1h1` in `CastlingMode::Chess960`).
+    /// Any other message is returned unchanged, since its moves can only be recognised as castling moves in the
+    /// context of a position. Use `convert_castling_after` to convert those.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vampirc_uci::{parse_one, CastlingMode, Serializable};
+    ///
+    /// let m = parse_one("position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1");
+    /// assert_eq!(
+    ///     m.convert_castling(CastlingMode::Chess960).serialize(),
+    ///     "position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1h1"
+    /// );
+    /// ```
+    pub fn convert_castling(&self, mode: CastlingMode) -> UciMessage {
+        match self {
+            UciMessage::Position { startpos, fen, moves } => UciMessage::Position {
+                startpos: *startpos,
+                fen: fen.clone(),
+                moves: convert_castling_moves(fen.as_ref(), moves, mode),
+            },
+            _ => self.clone()
         }
     }
 
-    /// Constructs an `info string ...` message.
-    pub fn info_string(s: String) -> UciMessage {
-        UciMessage::Info(vec![UciInfoAttribute::String(s)])
+    /// Returns a copy of this message with all the castling moves rewritten into the notation specified by `mode`, where
+    /// the moves of this message are played from the position described by `position`, a `UciMessage::Position`
+    /// (normally the last one sent to the engine). The moves of `bestmove` (and its `ponder` move), `go searchmoves`
+    /// and the `info` attributes `pv`, `currmove`, `refutation` and `currline` are converted. A `UciMessage::Position`
+    /// is converted like with `convert_castling`, as is every message if `position` is not a `UciMessage::Position`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use vampirc_uci::{parse_one, CastlingMode, Serializable};
+    ///
+    /// let position = parse_one("position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    /// let best_move = parse_one("bestmove e1h1 ponder e8a8");
+    /// assert_eq!(
+    ///     best_move.convert_castling_after(&position, CastlingMode::Standard).serialize(),
+    ///     "bestmove e1g1 ponder e8c8"
+    /// );
+    /// ```
+    pub fn convert_castling_after(&self, position: &UciMessage, mode: CastlingMode) -> UciMessage {
+        let (fen, played) = match position {
+            UciMessage::Position { fen, moves, .. } => (fen.as_ref(), moves.as_slice()),
+            _ => return self.convert_castling(mode),
+        };
+
+        // Converts a sequence of moves played after the moves of `position`.
+        let convert = |line: &[_]| {
+            let mut moves = played.to_vec();
+            moves.extend_from_slice(line);
+            convert_castling_moves(fen, &moves, mode).split_off(played.len())
+        };
+
+        match self {
+            UciMessage::BestMove { best_move, ponder } => {
+                let mut line = vec![*best_move];
+                line.extend(ponder);
+                let line = convert(&line);
+
+                UciMessage::BestMove {
+                    best_move: line[0],
+                    ponder: line.get(1).copied(),
+                }
+            }
+            UciMessage::Go { time_control, search_control } => UciMessage::Go {
+                time_control: time_control.clone(),
+                search_control: search_control.as_ref().map(|sc| UciSearchControl {
+                    search_moves: sc.search_moves.iter().map(|m| convert(&[*m])[0]).collect(),
+                    ..sc.clone()
+                }),
+            },
+            UciMessage::Info(attributes) => UciMessage::Info(
+                attributes
+                    .iter()
+                    .map(|a| match a {
+                        UciInfoAttribute::Pv(moves) => UciInfoAttribute::Pv(convert(moves)),
+                        UciInfoAttribute::Refutation(moves) => UciInfoAttribute::Refutation(convert(moves)),
+                        UciInfoAttribute::CurrMove(m) => UciInfoAttribute::CurrMove(convert(&[*m])[0]),
+                        UciInfoAttribute::CurrLine { cpu_nr, line } => UciInfoAttribute::CurrLine {
+                            cpu_nr: *cpu_nr,
+                            line: convert(line),
+                        },
+                        a => a.clone(),
+                    })
+                    .collect(),
+            ),
+            _ => self.convert_castling(mode),
+        }
     }
 
     /// Returns whether the command was meant for the engine or for the GUI.
@@ -288,9 +359,8 @@ impl UciMessage {
         match self {
             UciMessage::SetOption { value, .. } => {
                 if let Some(val) = value {
-                    let pr = str::parse(val.as_str());
-                    if pr.is_ok() {
-                        return Some(pr.unwrap());
+                    if let Ok(pr) = str::parse(val.as_str()) {
+                        return Some(pr);
                     }
                 }
 
@@ -306,9 +376,8 @@ impl UciMessage {
         match self {
             UciMessage::SetOption { value, .. } => {
                 if let Some(val) = value {
-                    let pr = str::parse(val.as_str());
-                    if pr.is_ok() {
-                        return Some(pr.unwrap());
+                    if let Ok(pr) = str::parse(val.as_str()) {
+                        return Some(pr);
                     }
                 }
 
@@ -320,10 +389,7 @@ impl UciMessage {
 
     /// Return `true` if this `UciMessage` is of variant `UnknownMessage`.
     pub fn is_unknown(&self) -> bool {
-        match self {
-            UciMessage::Unknown(..) => true,
-            _ => false
-        }
+        matches!(self, UciMessage::Unknown(..))
     }
 }
 
@@ -371,21 +437,21 @@ impl Serializable for UciMessage {
                     s += format!("fen {}", uci_fen.as_str()).as_str();
                 }
 
-                if moves.len() > 0 {
+                if !moves.is_empty() {
                     s += String::from(" moves").as_str();
 
                     for m in moves {
-                        s += format!(" {}", *m).as_str();
+                        s += format!(" {}", serialize_move(m)).as_str();
                     }
                 }
 
                 s
             }
             UciMessage::SetOption { name, value } => {
-                let mut s: String = String::from(format!("setoption name {}", name));
+                let mut s: String = format!("setoption name {}", name);
 
                 if let Some(val) = value {
-                    if val.len() == 0 {
+                    if val.is_empty() {
                         s += " value <empty>";
                     } else {
                         s += format!(" value {}", *val).as_str();
@@ -400,33 +466,36 @@ impl Serializable for UciMessage {
                 let mut s = String::from("go ");
 
                 if let Some(tc) = time_control {
-                    match tc {
-                        UciTimeControl::Infinite => { s += "infinite "; }
-                        UciTimeControl::Ponder => { s += "ponder "; }
-                        UciTimeControl::MoveTime(duration) => {
-                            s += format!("movetime {} ", duration.num_milliseconds()).as_str();
-                        }
-                        UciTimeControl::TimeLeft { white_time, black_time, white_increment, black_increment, moves_to_go } => {
-                            if let Some(wt) = white_time {
-                                s += format!("wtime {} ", wt.num_milliseconds()).as_str();
-                            }
-
-                            if let Some(bt) = black_time {
-                                s += format!("btime {} ", bt.num_milliseconds()).as_str();
-                            }
-
-                            if let Some(wi) = white_increment {
-                                s += format!("winc {} ", wi.num_milliseconds()).as_str();
-                            }
-
-                            if let Some(bi) = black_increment {
-                                s += format!("binc {} ", bi.num_milliseconds()).as_str();
-                            }
-
-                            if let Some(mtg) = moves_to_go {
-                                s += format!("movestogo {} ", *mtg).as_str();
-                            }
-                        }
+                    if tc.ponder {
+                        s += "ponder ";
+                    }
+
+                    if let Some(wt) = tc.white_time {
+                        s += format!("wtime {} ", wt.num_milliseconds()).as_str();
+                    }
+
+                    if let Some(bt) = tc.black_time {
+                        s += format!("btime {} ", bt.num_milliseconds()).as_str();
+                    }
+
+                    if let Some(wi) = tc.white_increment {
+                        s += format!("winc {} ", wi.num_milliseconds()).as_str();
+                    }
+
+                    if let Some(bi) = tc.black_increment {
+                        s += format!("binc {} ", bi.num_milliseconds()).as_str();
+                    }
+
+                    if let Some(mtg) = tc.moves_to_go {
+                        s += format!("movestogo {} ", mtg).as_str();
+                    }
+
+                    if let Some(duration) = tc.move_time {
+                        s += format!("movetime {} ", duration.num_milliseconds()).as_str();
+                    }
+
+                    if tc.infinite {
+                        s += "infinite ";
                     }
                 }
 
@@ -446,7 +515,7 @@ impl Serializable for UciMessage {
                     if !sc.search_moves.is_empty() {
                         s += " searchmoves ";
                         for m in &sc.search_moves {
-                            s += format!("{} ", m).as_str();
+                            s += format!("{} ", serialize_move(m)).as_str();
                         }
                     }
                 }
@@ -478,10 +547,10 @@ impl Serializable for UciMessage {
             UciMessage::UciOk => String::from("uciok"),
             UciMessage::ReadyOk => String::from("readyok"),
             UciMessage::BestMove { best_move, ponder } => {
-                let mut s = String::from(format!("bestmove {}", *best_move));
+                let mut s = format!("bestmove {}", serialize_move(best_move));
 
                 if let Some(p) = ponder {
-                    s += format!(" ponder {}", *p).as_str();
+                    s += format!(" ponder {}", serialize_move(p)).as_str();
                 }
 
                 s
@@ -521,67 +590,91 @@ impl Serializable for UciMessage {
 
 
 
-/// This enum represents the possible variants of the `go` UCI message that deal with the chess game's time controls
-/// and the engine's thinking time.
-#[derive(Clone, Eq, PartialEq, Debug, Hash)]
-pub enum UciTimeControl {
-    /// The `go ponder` message.
-    Ponder,
+/// This struct represents the parameters of the `go` UCI message that deal with the chess game's time controls and
+/// the engine's thinking time. The `ponder` and `infinite` flags, the clock values and `movetime` are independent of
+/// each other, so a message such as `go ponder wtime 60000 btime 60000` retains the clock information that the engine
+/// needs once the `ponderhit` message arrives.
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UciTimeControl {
+    /// The `go ponder` flag (search in pondering mode).
+    pub ponder: bool,
 
-    /// The `go infinite` message.
-    Infinite,
+    /// The `go infinite` flag (search until the `stop` message).
+    pub infinite: bool,
 
-    /// The information about the game's time controls.
-    TimeLeft {
-        /// White's time on the clock, in milliseconds.
-        white_time: Option<Duration>,
+    /// White's time on the clock, in milliseconds.
+    pub white_time: Option<UciDuration>,
 
-        /// Black's time on the clock, in milliseconds.
-        black_time: Option<Duration>,
+    /// Black's time on the clock, in milliseconds.
+    pub black_time: Option<UciDuration>,
 
-        /// White's increment per move, in milliseconds.
-        white_increment: Option<Duration>,
+    /// White's increment per move, in milliseconds.
+    pub white_increment: Option<UciDuration>,
 
-        /// Black's increment per move, in milliseconds.
-        black_increment: Option<Duration>,
+    /// Black's increment per move, in milliseconds.
+    pub black_increment: Option<UciDuration>,
 
-        /// The number of moves to go to the next time control.
-        moves_to_go: Option<u8>,
-    },
+    /// The number of moves to go to the next time control.
+    pub moves_to_go: Option<u16>,
 
     /// Specifies how much time the engine should think about the move, in milliseconds.
-    MoveTime(Duration)
+    pub move_time: Option<UciDuration>,
 }
 
 impl UciTimeControl {
-    /// Returns a `UciTimeControl::TimeLeft` with all members set to `None`.
-    pub fn time_left() -> UciTimeControl {
-        UciTimeControl::TimeLeft {
-            white_time: None,
-            black_time: None,
-            white_increment: None,
-            black_increment: None,
-            moves_to_go: None
+    /// Returns a `UciTimeControl` with the `ponder` flag set and everything else set to `false` or `None`.
+    pub fn ponder() -> UciTimeControl {
+        UciTimeControl {
+            ponder: true,
+            ..UciTimeControl::default()
+        }
+    }
+
+    /// Returns a `UciTimeControl` with the `infinite` flag set and everything else set to `false` or `None`.
+    pub fn infinite() -> UciTimeControl {
+        UciTimeControl {
+            infinite: true,
+            ..UciTimeControl::default()
+        }
+    }
+
+    /// Returns a `UciTimeControl` with `move_time` set to the parameter and everything else set to `false` or `None`.
+    pub fn move_time(milliseconds: UciDuration) -> UciTimeControl {
+        UciTimeControl {
+            move_time: Some(milliseconds),
+            ..UciTimeControl::default()
         }
     }
+
+    /// Returns a `UciTimeControl` with all members set to `false` or `None`, to be filled in with the clock values.
+    pub fn time_left() -> UciTimeControl {
+        UciTimeControl::default()
+    }
+
+    /// Returns `true` if any of the clock values (`wtime`, `btime`, `winc`, `binc` or `movestogo`) is set.
+    pub fn has_clock(&self) -> bool {
+        self.white_time.is_some()
+            || self.black_time.is_some()
+            || self.white_increment.is_some()
+            || self.black_increment.is_some()
+            || self.moves_to_go.is_some()
+    }
 }
 
 /// A struct that controls the engine's (non-time-related) search settings.
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UciSearchControl {
     /// Limits the search to these moves.
-    #[cfg(not(feature = "chess"))]
-    pub search_moves: Vec<UciMove>,
-
-    /// Limits the search to these moves.
-    #[cfg(feature = "chess")]
-    pub search_moves: Vec<ChessMove>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::moves"))]
+    pub search_moves: Vec<Move>,
 
     /// Search for mate in this many moves.
-    pub mate: Option<u8>,
+    pub mate: Option<i16>,
 
     /// Search to this ply depth.
-    pub depth: Option<u8>,
+    pub depth: Option<u16>,
 
     /// Search no more than this many nodes (positions).
     pub nodes: Option<u64>,
@@ -589,7 +682,7 @@ pub struct UciSearchControl {
 
 impl UciSearchControl {
     /// Creates an `UciSearchControl` with `depth` set to the parameter and everything else set to empty or `None`.
-    pub fn depth(depth: u8) -> UciSearchControl {
+    pub fn depth(depth: u16) -> UciSearchControl {
         UciSearchControl {
             search_moves: vec![],
             mate: None,
@@ -599,7 +692,7 @@ impl UciSearchControl {
     }
 
     /// Creates an `UciSearchControl` with `mate` set to the parameter and everything else set to empty or `None`.
-    pub fn mate(mate: u8) -> UciSearchControl {
+    pub fn mate(mate: i16) -> UciSearchControl {
         UciSearchControl {
             search_moves: vec![],
             mate: Some(mate),
@@ -638,6 +731,8 @@ impl Default for UciSearchControl {
 
 /// Represents the copy protection or registration state.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum ProtectionState {
     /// Signifies the engine is checking the copy protection or registration.
     Checking,
@@ -651,6 +746,8 @@ pub enum ProtectionState {
 
 /// Represents a UCI option definition.
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "lowercase"))]
 pub enum UciOptionConfig {
     /// The option of type `check` (a boolean).
     Check {
@@ -741,7 +838,7 @@ impl Serializable for UciOptionConfig {
     /// assert_eq!(m.serialize(), "option name Nullmove type check default true");
     /// ```
     fn serialize(&self) -> String {
-        let mut s = String::from(format!("option name {} type {}", self.get_name(), self.get_type_str()));
+        let mut s = format!("option name {} type {}", self.get_name(), self.get_type_str());
         match self {
             UciOptionConfig::Check { default, .. } => {
                 if let Some(def) = default {
@@ -793,26 +890,24 @@ impl Display for UciOptionConfig {
 /// The representation of various info messages. For an info attribute that is not listed in the protocol specification,
 /// the `UciInfoAttribute::Any(name, value)` variant can be used.
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value", rename_all = "lowercase"))]
 pub enum UciInfoAttribute {
     /// The `info depth` message.
-    Depth(u8),
+    Depth(u16),
 
     /// The `info seldepth` message.
-    SelDepth(u8),
+    SelDepth(u16),
 
     /// The `info time` message.
-    Time(Duration),
+    Time(UciDuration),
 
     /// The `info nodes` message.
     Nodes(u64),
 
     /// The `info pv` message (best line move sequence).
-    #[cfg(not(feature = "chess"))]
-    Pv(Vec<UciMove>),
-
-    /// The `info pv` message (best line move sequence).
-    #[cfg(feature = "chess")]
-    Pv(Vec<ChessMove>),
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::moves"))]
+    Pv(Vec<Move>),
 
     /// The `info pv ... multipv` message (the pv line number in a multi pv sequence).
     MultiPv(u16),
@@ -823,7 +918,7 @@ pub enum UciInfoAttribute {
         cp: Option<i32>,
 
         /// Mate coming up in this many moves. Negative value means the engine is getting mated.
-        mate: Option<i8>,
+        mate: Option<i16>,
 
         /// The value sent is the lower bound.
         lower_bound: Option<bool>,
@@ -832,15 +927,25 @@ pub enum UciInfoAttribute {
         upper_bound: Option<bool>,
     },
 
-    /// The `info currmove` message (current move).
-    #[cfg(not(feature = "chess"))]
-    CurrMove(UciMove),
+    /// The `info wdl` message (win/draw/loss statistics in permills, sent by engines with `UCI_ShowWDL` enabled).
+    Wdl {
+        /// The expected number of wins per thousand games.
+        win: u16,
+
+        /// The expected number of draws per thousand games.
+        draw: u16,
+
+        /// The expected number of losses per thousand games.
+        loss: u16,
+    },
 
     /// The `info currmove` message (current move).
-    #[cfg(feature = "chess")]
-    CurrMove(ChessMove),
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::a_move"))]
+    CurrMove(Move),
 
-    /// The `info currmovenum` message (current move number).
+    /// The `info currmovenumber` message (current move number). The non-standard short form `currmovenum` is
+    /// accepted by the parser as well.
+    #[cfg_attr(feature = "serde", serde(rename = "currmovenumber"))]
     CurrMoveNum(u16),
 
     /// The `info hashfull` message (the occupancy of hashing tables in permills).
@@ -863,12 +968,8 @@ pub enum UciInfoAttribute {
     String(String),
 
     /// The `info refutation` message (the first move is the move being refuted).
-    #[cfg(not(feature = "chess"))]
-    Refutation(Vec<UciMove>),
-
-    /// The `info refutation` message (the first move is the move being refuted).
-    #[cfg(feature = "chess")]
-    Refutation(Vec<ChessMove>),
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::moves"))]
+    Refutation(Vec<Move>),
 
     /// The `info currline` message (current line being calculated on a CPU).
     CurrLine {
@@ -876,15 +977,14 @@ pub enum UciInfoAttribute {
         cpu_nr: Option<u16>,
 
         /// The line being calculated.
-        #[cfg(not(feature = "chess"))]
-        line: Vec<UciMove>,
-
-        /// The line being calculated.
-        #[cfg(feature = "chess")]
-        line: Vec<ChessMove>,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::moves"))]
+        line: Vec<Move>,
     },
 
-    /// Any other info line in the format `(name, value)`.
+    /// Any other info attribute in the format `(name, value)`. The value spans all the tokens up to the next known
+    /// info attribute, and is empty if the attribute is a lone flag. Since the parser cannot tell the name of a
+    /// vendor-specific attribute from a value, consecutive unknown attributes are merged into one: `ebf 1.8 foo 3`
+    /// becomes `Any("ebf", "1.8 foo 3")`.
     Any(String, String),
 }
 
@@ -902,7 +1002,7 @@ impl UciInfoAttribute {
 
     /// Creates a `UciInfoAttribute::Score` with the `mate` attribute set to the value of the parameter and all other
     /// fields set to `None`. A negative value indicates it is the engine that is getting mated.
-    pub fn from_mate(mate: i8) -> UciInfoAttribute {
+    pub fn from_mate(mate: i16) -> UciInfoAttribute {
         UciInfoAttribute::Score {
             cp: None,
             mate: Some(mate),
@@ -921,8 +1021,9 @@ impl UciInfoAttribute {
             UciInfoAttribute::Pv(..) => "pv",
             UciInfoAttribute::MultiPv(..) => "multipv",
             UciInfoAttribute::Score { .. } => "score",
+            UciInfoAttribute::Wdl { .. } => "wdl",
             UciInfoAttribute::CurrMove(..) => "currmove",
-            UciInfoAttribute::CurrMoveNum(..) => "currmovenum",
+            UciInfoAttribute::CurrMoveNum(..) => "currmovenumber",
             UciInfoAttribute::HashFull(..) => "hashfull",
             UciInfoAttribute::Nps(..) => "nps",
             UciInfoAttribute::TbHits(..) => "tbhits",
@@ -939,7 +1040,7 @@ impl UciInfoAttribute {
 impl Serializable for UciInfoAttribute {
     /// Returns the attribute serialized as a String.
     fn serialize(&self) -> String {
-        let mut s = format!("{}", self.get_name());
+        let mut s = self.get_name().to_string();
         match self {
             UciInfoAttribute::Depth(depth) => s += format!(" {}", *depth).as_str(),
             UciInfoAttribute::SelDepth(depth) => s += format!(" {}", *depth).as_str(),
@@ -948,7 +1049,7 @@ impl Serializable for UciInfoAttribute {
             UciInfoAttribute::Pv(moves) | UciInfoAttribute::Refutation(moves) => {
                 if !moves.is_empty() {
                     for m in moves {
-                        s += format!(" {}", m).as_str();
+                        s += format!(" {}", serialize_move(m)).as_str();
                     }
                 }
             },
@@ -968,13 +1069,18 @@ impl Serializable for UciInfoAttribute {
                     s += " upperbound";
                 }
             },
-            UciInfoAttribute::CurrMove(uci_move) => s += &format!(" {}", *uci_move),
+            UciInfoAttribute::Wdl { win, draw, loss } => s += &format!(" {} {} {}", *win, *draw, *loss),
+            UciInfoAttribute::CurrMove(uci_move) => s += &format!(" {}", serialize_move(uci_move)),
             UciInfoAttribute::CurrMoveNum(num) => s += &format!(" {}", *num),
             UciInfoAttribute::HashFull(permill) => s += &format!(" {}", *permill),
             UciInfoAttribute::Nps(nps) => s += &format!(" {}", *nps),
             UciInfoAttribute::TbHits(hits) | UciInfoAttribute::SbHits(hits) => s += &format!(" {}", *hits),
             UciInfoAttribute::CpuLoad(load) => s += &format!(" {}", *load),
-            UciInfoAttribute::String(string) => s += &format!(" {}", string),
+            UciInfoAttribute::String(string) => {
+                if !string.is_empty() {
+                    s += &format!(" {}", string);
+                }
+            }
             UciInfoAttribute::CurrLine { cpu_nr, line } => {
                 if let Some(c) = cpu_nr {
                     s += &format!(" cpunr {}", *c);
@@ -982,12 +1088,14 @@ impl Serializable for UciInfoAttribute {
 
                 if !line.is_empty() {
                     for m in line {
-                        s += &format!(" {}", m);
+                        s += &format!(" {}", serialize_move(m));
                     }
                 }
             },
             UciInfoAttribute::Any(_, value) => {
-                s += &format!(" {}", value);
+                if !value.is_empty() {
+                    s += &format!(" {}", value);
+                }
             }
         }
 
@@ -1003,6 +1111,8 @@ impl Display for UciInfoAttribute {
 
 /// An enum representing the chess piece types.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 #[cfg(not(feature = "chess"))]
 pub enum UciPiece {
     Pawn,
@@ -1062,6 +1172,16 @@ impl FromStr for UciPiece {
     }
 }
 
+/// An enum representing the side (colour) of a piece or the side to move.
+#[cfg(not(feature = "chess"))]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum UciColor {
+    White,
+    Black,
+}
+
 /// A representation of a chessboard square.
 #[cfg(not(feature = "chess"))]
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
@@ -1127,6 +1247,22 @@ impl UciMove {
             promotion: None,
         }
     }
+
+    /// Creates the null move, serialized as `0000`. Engines send it (or `(none)`) as their `bestmove` when there is no
+    /// legal move in the position, ie. when they are mated or stalemated. Both of its squares are the (invalid)
+    /// default `UciSquare`.
+    pub fn null() -> UciMove {
+        UciMove {
+            from: UciSquare::default(),
+            to: UciSquare::default(),
+            promotion: None,
+        }
+    }
+
+    /// Returns `true` if this is the null move (`0000`).
+    pub fn is_null(&self) -> bool {
+        *self == UciMove::null()
+    }
 }
 
 #[cfg(not(feature = "chess"))]
@@ -1135,7 +1271,12 @@ impl Display for UciMove {
     ///
     /// `e2e4` – A move from the square `e2` to the square `e4`.
     /// `a2a1q` – A move from the square `a2` to the square `a1` with the pawn promoting to a Queen..
+    /// `0000` – The null move.
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if self.is_null() {
+            return write!(f, "0000");
+        }
+
         let mut r = write!(f, "{}{}", self.from, self.to);
 
         if let Some(p) = self.promotion {
@@ -1148,8 +1289,48 @@ impl Display for UciMove {
     }
 }
 
+/// The type of the moves in the messages: `UciMove`, or `chess::ChessMove` with the `chess` feature.
+#[cfg(not(feature = "chess"))]
+pub type Move = UciMove;
+
+/// The type of the moves in the messages: `UciMove`, or `chess::ChessMove` with the `chess` feature.
+#[cfg(feature = "chess")]
+pub type Move = ChessMove;
+
+/// Returns the `chess` crate representation of the null move (`0000`), which is `ChessMove::default()` (`a1a1`).
+/// Engines send the null move (or `(none)`) as their `bestmove` when there is no legal move in the position.
+#[cfg(feature = "chess")]
+pub fn null_move() -> ChessMove {
+    ChessMove::default()
+}
+
+/// Returns `true` if the `ChessMove` represents the null move (`0000`), ie. if it is equal to `null_move()`. Other
+/// moves whose source and destination squares are the same are not null moves.
+#[cfg(feature = "chess")]
+pub fn is_null_move(m: &ChessMove) -> bool {
+    *m == null_move()
+}
+
+/// Formats a move in the UCI move notation, writing the null move as `0000`.
+#[cfg(feature = "chess")]
+pub(crate) fn serialize_move(m: &ChessMove) -> String {
+    if is_null_move(m) {
+        String::from("0000")
+    } else {
+        m.to_string()
+    }
+}
+
+/// Formats a move in the UCI move notation, writing the null move as `0000`.
+#[cfg(not(feature = "chess"))]
+pub(crate) fn serialize_move(m: &UciMove) -> String {
+    m.to_string()
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Hash)]
 /// A representation of the notation in the [FEN notation](https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct UciFen(pub String);
 
 impl UciFen {
@@ -1158,6 +1339,54 @@ impl UciFen {
     pub fn as_str(&self) -> &str {
         self.0.as_str()
     }
+
+    /// Returns `true` if the castling field of this FEN is consistent with its piece placement. Standard (`KQkq`),
+    /// Shredder-FEN (`HAha`) and [X-FEN](https://en.wikipedia.org/wiki/X-FEN) castling rights are supported.
+    ///
+    /// The parser only checks the syntax of the castling field, so that positions with stale castling rights (as sent
+    /// by some GUIs) are not rejected. Callers that need valid castling rights must check them with this method.
+    pub fn has_valid_castling_rights(&self) -> bool {
+        castling_rights_valid(self.as_str())
+    }
+
+    /// Returns `true` if this FEN contains the halfmove clock (the fifth field). GUIs and EPD-derived tools often send
+    /// FENs without the move counters.
+    pub fn has_halfmove_clock(&self) -> bool {
+        self.field(4).is_some()
+    }
+
+    /// Returns `true` if this FEN contains the fullmove number (the sixth field).
+    pub fn has_fullmove_number(&self) -> bool {
+        self.field(5).is_some()
+    }
+
+    /// Returns the halfmove clock, or `0` if the FEN does not contain it.
+    pub fn halfmove_clock(&self) -> u16 {
+        self.field(4).and_then(|f| f.parse().ok()).unwrap_or(0)
+    }
+
+    /// Returns the fullmove number, or `1` if the FEN does not contain it.
+    pub fn fullmove_number(&self) -> u16 {
+        self.field(5).and_then(|f| f.parse().ok()).unwrap_or(1)
+    }
+
+    /// Returns this FEN with all six fields, filling in the default halfmove clock (`0`) and fullmove number (`1`) if
+    /// they are missing. The `UciFen` itself keeps the FEN as it was received, so that it is serialized faithfully.
+    pub fn normalized(&self) -> UciFen {
+        let fields: Vec<&str> = self.0.split_whitespace().take(4).collect();
+
+        UciFen(format!("{} {} {}", fields.join(" "), self.halfmove_clock(), self.fullmove_number()))
+    }
+
+    /// Parses this FEN into a structured `FenPosition`, with the piece placement, the side to move, the castling
+    /// rights, the en passant square and the move counters.
+    pub fn position(&self) -> Result<FenPosition, UciParseError> {
+        self.as_str().parse()
+    }
+
+    fn field(&self, index: usize) -> Option<&str> {
+        self.0.split_whitespace().nth(index)
+    }
 }
 
 impl From<&str> for UciFen {
@@ -1204,9 +1433,9 @@ impl From<UciMessage> for ByteVecUciMessage {
     }
 }
 
-impl Into<UciMessage> for ByteVecUciMessage {
-    fn into(self) -> UciMessage {
-        self.message
+impl From<ByteVecUciMessage> for UciMessage {
+    fn from(m: ByteVecUciMessage) -> Self {
+        m.message
     }
 }
 
@@ -1225,7 +1454,7 @@ impl AsRef<[u8]> for ByteVecUciMessage {
 #[cfg(test)]
 mod tests {
     #[cfg(feature = "chess")]
-    use chess::Square;
+    use chess::{Piece, Square};
 
     use super::*;
 
@@ -1286,6 +1515,25 @@ mod tests {
         ).serialize().as_str(), "bestmove b4a5 ponder b4d6");
     }
 
+    #[cfg(not(feature = "chess"))]
+    #[test]
+    fn test_serialize_null_move() {
+        assert!(UciMove::null().is_null());
+        assert!(!UciMove::from_to(UciSquare::from('e', 2), UciSquare::from('e', 4)).is_null());
+        assert_eq!(UciMessage::best_move(UciMove::null()).serialize().as_str(), "bestmove 0000");
+    }
+
+    #[cfg(feature = "chess")]
+    #[test]
+    fn test_serialize_null_move() {
+        assert!(is_null_move(&null_move()));
+        assert!(!is_null_move(&ChessMove::new(Square::E2, Square::E4, None)));
+        assert!(!is_null_move(&ChessMove::new(Square::E1, Square::E1, None)));
+        assert!(!is_null_move(&ChessMove::new(Square::A1, Square::A1, Some(Piece::Queen))));
+        assert_eq!(UciMessage::best_move(null_move()).serialize().as_str(), "bestmove 0000");
+        assert_eq!(UciMessage::best_move(ChessMove::new(Square::E1, Square::E1, None)).serialize(), "bestmove e1e1");
+    }
+
     #[test]
     fn test_serialize_copyprotection() {
         assert_eq!(UciMessage::CopyProtection(ProtectionState::Checking).serialize().as_str(), "copyprotection checking");
@@ -1377,7 +1625,7 @@ mod tests {
         let attributes: Vec<UciInfoAttribute> = vec![
             UciInfoAttribute::Depth(2),
             UciInfoAttribute::from_centipawns(214),
-            UciInfoAttribute::Time(Duration::milliseconds(1242)),
+            UciInfoAttribute::Time(UciDuration::milliseconds(1242)),
             UciInfoAttribute::Nodes(2124),
             UciInfoAttribute::Nps(34928),
             #[cfg(not(feature = "chess"))]
@@ -1410,7 +1658,7 @@ mod tests {
             UciInfoAttribute::Nodes(1540),
             UciInfoAttribute::Nps(54),
             UciInfoAttribute::TbHits(0),
-            UciInfoAttribute::Time(Duration::milliseconds(28098)),
+            UciInfoAttribute::Time(UciDuration::milliseconds(28098)),
             #[cfg(not(feature = "chess"))]
                 UciInfoAttribute::Pv(vec![
                 UciMove::from_to(UciSquare::from('a', 8), UciSquare::from('b', 6)),
@@ -1466,6 +1714,22 @@ mod tests {
         assert_eq!(m.serialize(), "info score mate -3");
     }
 
+    #[test]
+    fn test_serialize_info_wdl() {
+        let attributes: Vec<UciInfoAttribute> = vec![
+            UciInfoAttribute::from_centipawns(35),
+            UciInfoAttribute::Wdl {
+                win: 412,
+                draw: 530,
+                loss: 58,
+            }
+        ];
+
+        let m = UciMessage::Info(attributes);
+
+        assert_eq!(m.serialize(), "info score cp 35 wdl 412 530 58");
+    }
+
     #[test]
     fn test_serialize_info_currmove() {
         #[cfg(not(feature = "chess"))]
@@ -1505,7 +1769,7 @@ mod tests {
 
         let m = UciMessage::Info(attributes);
 
-        assert_eq!(m.serialize(), "info currmove a2f2 currmovenum 2");
+        assert_eq!(m.serialize(), "info currmove a2f2 currmovenumber 2");
     }
 
     #[test]
@@ -1657,13 +1921,13 @@ mod tests {
 
     #[test]
     fn test_is_unknown_false() {
-        assert_eq!(UciMessage::Uci.is_unknown(), false);
+        assert!(!UciMessage::Uci.is_unknown());
     }
 
     #[test]
     fn test_is_unknown_true() {
         let um = UciMessage::Unknown("Unrecognized Command".to_owned(), None);
-        assert_eq!(um.is_unknown(), true);
+        assert!(um.is_unknown());
     }
 
     #[test]
@@ -1697,14 +1961,30 @@ mod tests {
         assert_eq!(empty_go, UciMessage::Go { time_control: None, search_control: None });
     }
 
+    #[test]
+    fn test_serialize_go_ponder_with_clock() {
+        let message = UciMessage::Go {
+            time_control: Some(UciTimeControl {
+                ponder: true,
+                white_time: Some(UciDuration::milliseconds(60000)),
+                black_time: Some(UciDuration::milliseconds(60000)),
+                white_increment: Some(UciDuration::milliseconds(1000)),
+                black_increment: Some(UciDuration::milliseconds(1000)),
+                ..UciTimeControl::default()
+            }),
+            search_control: None,
+        };
+
+        assert_eq!(message.serialize().trim_end(), "go ponder wtime 60000 btime 60000 winc 1000 binc 1000");
+    }
+
     #[test]
     fn test_negative_duration() {
-        let time_control = UciTimeControl::TimeLeft {
-            white_time: Some(Duration::milliseconds(-4061)),
-            black_time: Some(Duration::milliseconds(56826)),
-            white_increment: None,
-            black_increment: None,
+        let time_control = UciTimeControl {
+            white_time: Some(UciDuration::milliseconds(-4061)),
+            black_time: Some(UciDuration::milliseconds(56826)),
             moves_to_go: Some(90),
+            ..UciTimeControl::default()
         };
 
         let message = UciMessage::Go {
@@ -1715,18 +1995,30 @@ mod tests {
         match message {
             UciMessage::Go { time_control, search_control: _ } => {
                 let tc = time_control.unwrap();
-                match tc {
-                    UciTimeControl::TimeLeft { white_time, black_time, white_increment: _, black_increment: _, moves_to_go: _ } => {
-                        let wt = white_time.unwrap();
-                        assert_eq!(wt, Duration::milliseconds(-4061));
-                        assert_eq!(wt.num_milliseconds(), -4061);
-                        assert_eq!(wt.num_seconds(), -4);
-                        assert_eq!(black_time.unwrap(), Duration::milliseconds(56826));
-                    }
-                    _ => unreachable!()
-                }
+                let wt = tc.white_time.unwrap();
+                assert_eq!(wt, UciDuration::milliseconds(-4061));
+                assert_eq!(wt.num_milliseconds(), -4061);
+                assert_eq!(wt.num_seconds(), -4);
+                assert_eq!(tc.black_time.unwrap(), UciDuration::milliseconds(56826));
             },
             _ => unreachable!()
         }
     }
+
+    #[test]
+    fn test_fen_counters() {
+        let full = UciFen::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3 17");
+        assert!(full.has_halfmove_clock());
+        assert!(full.has_fullmove_number());
+        assert_eq!(full.halfmove_clock(), 3);
+        assert_eq!(full.fullmove_number(), 17);
+        assert_eq!(full.normalized(), full);
+
+        let short = UciFen::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -");
+        assert!(!short.has_halfmove_clock());
+        assert_eq!(short.halfmove_clock(), 0);
+        assert_eq!(short.fullmove_number(), 1);
+        assert_eq!(short.normalized().as_str(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(short.to_string(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -");
+    }
 }